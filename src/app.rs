@@ -1,24 +1,179 @@
-use std::{fs::{self, OpenOptions}, io::Write, process::Command};
-
-use crate::lang::{get_langs, Lang};
+use std::{env, fs, io::Write, os::unix::fs::PermissionsExt, process::{Command, Output, Stdio}, sync::Arc, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+use std::path::Path;
+
+use crate::{
+    connectivity::{check, detect_targets, Connectivity, PROBE_TIMEOUT},
+    host::hosts_file,
+    hyprland::{hyprland_conf, DEFAULT_CONFIG},
+    keyboard::{keymap_list, layout_list, split_layout, vconsole_conf, xorg_keyboard_conf, Keyboard, KEYMAPS},
+    keyring::{iso_date, keyring_age, run_step, today},
+    lang::{detect_lang, get_langs, Lang},
+    locale::{locale_conf, locale_gen, locale_list, CATEGORIES},
+    mirror::{countries, fetch_mirrorlist, mirrorlist, parse_mirrorlist, rank, Mirror, FETCH_TIMEOUT, MIRRORLIST, MIRRORLIST_ALL, RANK_TIMEOUT},
+    network::{
+        address, apply_manual, detect_backend, dhcp, has_address, hidden_security, interface_list, iwd_profile_name, networkd_conf, move_ca_cert, nm_keyfile_name,
+        nm_manual_keyfile, nm_wifi_keyfile, profile_from_iwd, proxy_environment, set_proxy,
+        Enterprise, Interface, Link, Manual, Network, NetworkBackend, NetworkError, Persist, Security, WifiProfile, IWD_DIR, NM_CERTS, SYS_NET,
+    },
+    timesync::{detect_ntp_servers, synchronized, timesyncd_conf, TIMESYNCD_CONF, TIME_SYNC_TIMEOUT},
+    timezone::{zone_list, ZONEINFO},
+    user::{validate_username, NameError, User},
+};
 
 
 pub enum Screen {
     LanguageSelection,
+    Keyboard,
+    Network,
     WifiSelection,
+    Mirrors,
     DiskSelection,
     Partitioning,
     Efipart,
+    HostSetup,
+    Timezone,
+    Locale,
+    UserList,
     UserSetup,
     Installing,
 }
 
+#[derive(Clone)]
+pub enum KeyboardPicker {
+    Keymap,
+    Layout,
+}
+
 #[derive(Clone)]
 pub enum Editing {
     Name,
     Password,
+    PasswordRepeat,
+    Admin,
+    Groups,
+    Shell,
+}
+impl Editing {
+    pub fn next(&self) -> Self {
+        match self {
+            Editing::Name => Editing::Password,
+            Editing::Password => Editing::PasswordRepeat,
+            Editing::PasswordRepeat => Editing::Admin,
+            Editing::Admin => Editing::Groups,
+            Editing::Groups | Editing::Shell => Editing::Shell,
+        }
+    }
+    pub fn prev(&self) -> Self {
+        match self {
+            Editing::Name | Editing::Password => Editing::Name,
+            Editing::PasswordRepeat => Editing::Password,
+            Editing::Admin => Editing::PasswordRepeat,
+            Editing::Groups => Editing::Admin,
+            Editing::Shell => Editing::Groups,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum WifiField {
+    Ssid,
+    Eap,
+    Identity,
+    Password,
+    CaCert,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum ManualField {
+    Address,
+    Gateway,
+    Dns,
+    Proxy,
+    Persist,
+}
+impl ManualField {
+    pub fn next(&self) -> Self {
+        match self {
+            ManualField::Address => ManualField::Gateway,
+            ManualField::Gateway => ManualField::Dns,
+            ManualField::Dns => ManualField::Proxy,
+            ManualField::Proxy | ManualField::Persist => ManualField::Persist,
+        }
+    }
+    pub fn prev(&self) -> Self {
+        match self {
+            ManualField::Address | ManualField::Gateway => ManualField::Address,
+            ManualField::Dns => ManualField::Gateway,
+            ManualField::Proxy => ManualField::Dns,
+            ManualField::Persist => ManualField::Proxy,
+        }
+    }
+}
+
+pub enum TimeStatus {
+    Idle,
+    Syncing(Instant),
+    Synced,
+    Failed,
+}
+
+pub enum WifiStatus {
+    Idle,
+    Connecting(Instant),
+    Failed(Log),
+}
+
+// Association and DHCP together, iwd gives up on a network on its own well before that
+const WIFI_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn search<'a>(items: impl Iterator<Item = &'a String>, text: &str) -> Vec<String> {
+    let text = text.to_lowercase();
+    items
+        .filter(|item| item.to_lowercase().replace('_', " ").contains(&text) || item.to_lowercase().contains(&text))
+        .cloned()
+        .collect()
+}
+
+
+#[derive(Clone)]
+pub enum LogArg {
+    Text(String),
+    // A translation key, looked up when the log is drawn so it follows the language
+    Key(String),
+}
+
+#[derive(Clone)]
+pub struct Log {
+    pub key: String,
+    pub args: Vec<LogArg>,
+}
+impl Log {
+    pub fn new(key: &str, args: &[&str]) -> Self {
+        Self {
+            key: key.to_string(),
+            args: args.iter().map(|arg| LogArg::Text(arg.to_string())).collect(),
+        }
+    }
+
+    // Appended after the plain arguments
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.args.push(LogArg::Key(key.to_string()));
+        self
+    }
+
+    pub fn text(&self, language: &Lang) -> String {
+        let args: Vec<String> = self.args.iter()
+            .map(|arg| match arg {
+                LogArg::Text(text) => text.clone(),
+                LogArg::Key(key) => language.get(key),
+            })
+            .collect();
+        language.fmt(&self.key, &args)
+    }
 }
 
+
 pub struct Data {
     pub name: String,
     pub password: String,
@@ -35,171 +190,763 @@ impl Data {
 
 pub struct App {
     pub install_list: Vec<(String, Vec<String>)>,
-    pub user: Data,
+    pub users: Vec<User>,
+    pub user: User,
+    pub user_index: Option<usize>,
+    pub password_repeat: String,
+    pub group_select: usize,
+    pub hostname: String,
+    pub timezone_list: Vec<(String, Vec<String>)>,
+    pub timezone_region: Option<String>,
+    pub timezone_search: String,
+    pub timezone: String,
+    pub rtc_local: bool,
+    pub locale_list: Vec<(String, String)>,
+    pub locale_values: Vec<String>,
+    pub locale_category: Option<usize>,
+    pub locale_search: String,
     pub disk_list: Vec<(String, Vec<String>)>,
     pub disk_tom_efi: Option<String>,
     pub disk_tom: String,
     pub disk: usize,
+    pub network: Option<Arc<dyn NetworkBackend>>,
+    pub interfaces: Vec<Interface>,
+    pub manual: Option<Manual>,
+    pub manual_field: Option<ManualField>,
     pub wifi_device: String,
-    pub wifi_list: Vec<String>,
+    pub wifi_list: Vec<Network>,
     pub wifi: Data,
+    pub wifi_enterprise: Enterprise,
+    pub wifi_field: Option<WifiField>,
+    pub wifi_status: WifiStatus,
+    pub wifi_thread: Option<JoinHandle<Result<(), NetworkError>>>,
+    pub wifi_profile: Option<WifiProfile>,
+    pub probe_targets: Vec<String>,
+    pub probe_thread: Option<JoinHandle<Connectivity>>,
+    pub connectivity: Connectivity,
+    pub ntp_servers: String,
+    // From the manual network form, handed to every command that downloads
+    pub proxy: String,
+    pub mirror_list: Vec<Mirror>,
+    pub mirror_search: String,
+    pub mirror_country: Option<String>,
+    // None while the ranking runs
+    pub mirror_ranked: Option<Vec<(Mirror, Duration)>>,
+    pub mirror_chosen: Vec<Mirror>,
+    pub time_status: TimeStatus,
+    // Enter was pressed once on install while the clock was not synced
+    pub time_confirmed: bool,
+    pub keyboard: Keyboard,
+    pub keymap_list: Vec<String>,
+    pub layout_list: Vec<(String, String)>,
+    pub keyboard_picker: Option<KeyboardPicker>,
+    pub keyboard_search: String,
     pub language_list: Vec<Lang>,
+    // The installer speaks `language`, the installed system gets `target_language`
     pub language: Lang,
+    pub target_language: Lang,
     pub screen: Screen,
     pub editing: Option<Editing>,
     pub select_num: usize,
     pub debug_mode: bool,
-    pub logs: Vec<String>,
+    pub logs: Vec<Log>,
 }
 
 impl App {
     pub fn new() -> Self {
-        Self {
+        let mut app = Self {
             install_list: Vec::new(),
-            user: Data::new(),
+            users: Vec::new(),
+            user: User::new(),
+            user_index: None,
+            password_repeat: String::new(),
+            group_select: 0,
+            hostname: String::new(),
+            timezone_list: Vec::new(),
+            timezone_region: None,
+            timezone_search: String::new(),
+            timezone: String::new(),
+            rtc_local: false,
+            locale_list: Vec::new(),
+            locale_values: vec![String::new(); CATEGORIES.len()],
+            locale_category: None,
+            locale_search: String::new(),
             disk_list: Vec::new(),
             disk_tom_efi: None,
             disk_tom: String::new(),
             disk: 0,
+            network: None,
+            interfaces: Vec::new(),
+            manual: None,
+            manual_field: None,
             wifi_device: String::new(),
             wifi_list: Vec::new(),
             wifi: Data::new(),
+            wifi_enterprise: Enterprise::new(),
+            wifi_field: None,
+            wifi_status: WifiStatus::Idle,
+            wifi_thread: None,
+            wifi_profile: None,
+            probe_targets: detect_targets(),
+            probe_thread: None,
+            connectivity: Connectivity::Failed(String::new()),
+            ntp_servers: detect_ntp_servers(),
+            proxy: String::new(),
+            mirror_list: Vec::new(),
+            mirror_search: String::new(),
+            mirror_country: None,
+            mirror_ranked: None,
+            mirror_chosen: Vec::new(),
+            time_status: TimeStatus::Idle,
+            time_confirmed: false,
+            keyboard: Keyboard::new(),
+            keymap_list: Vec::new(),
+            layout_list: Vec::new(),
+            keyboard_picker: None,
+            keyboard_search: String::new(),
             language_list: get_langs(),
             language: Lang::en(),
+            target_language: Lang::en(),
             screen: Screen::LanguageSelection,
             editing: None,
             select_num: 0,
             debug_mode: false,
             logs: Vec::new(),
+        };
+
+        if let Some(index) = detect_lang(&app.language_list) {
+            app.select_num = index;
+            app.set_language(index);
+            app.logs.push(Log::new("log_language_detect", &[&app.target_language.name]));
         }
+        app.logs.push(Log::new("log_probe_targets", &[&app.probe_targets.join(", ")]));
+        app
     }
 
 
-    pub fn wifi_check(&mut self) -> bool {
-        Command::new("ping")
-            .arg("-c")
-            .arg("1")
-            .arg("8.8.8.8")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
+    // The Linux console has no CJK glyphs, there the installer falls back and only the target gets the language
+    pub fn set_language(&mut self, index: usize) {
+        let lang = self.language_list.get(index).unwrap_or_else(|| self.language.fail("error_state_language", &[])).clone();
+        let fallback = lang.get("console_fallback");
+        let on_console = env::var("TERM").is_ok_and(|term| term == "linux");
+
+        self.language = match self.language_list.iter().find(|item| item.name == fallback) {
+            Some(item) if on_console => item.clone(),
+            _ => lang.clone(),
+        };
+        self.target_language = lang;
+        self.set_console_font();
+    }
+    pub fn language_fallback(&self) -> bool {
+        self.language.name != self.target_language.name
+    }
+
+    // Without arguments setfont goes back to the default font, which has no Cyrillic
+    pub fn set_console_font(&mut self) {
+        let font = self.language.get("console_font");
+        let mut command = Command::new("setfont");
+        if !font.is_empty() {
+            command.arg(&font);
+        }
+
+        let status = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
             .status()
             .map(|s| s.success())
-            .unwrap_or(false)
+            .unwrap_or(false);
+        if status {
+            self.logs.push(Log::new("log_font_load", &[&font]));
+        } else {
+            self.logs.push(Log::new("log_font_load_failed", &[&font]));
+        }
     }
-    pub fn set_wifi_list(&mut self) {
-        if self.wifi_device == String::new() {
-            let output = Command::new("iwctl")
-                .args(["station", "list"])
-                .output()
-                .expect("failed to run iwctl");
-            let devices_list_text = String::from_utf8_lossy(&output.stdout);
-
-            for device_text in devices_list_text.lines().skip(3) {
-                if let Some((_, after)) = device_text.split_once("  ") {
-                    if let Some((before, _)) = after.split_once(" ") {
-                        self.logs.push("Network device detect: ".to_string() + before);
 
-                        self.wifi_device = before.to_string();
-                        break;
+    pub fn set_keyboard_lists(&mut self) {
+        if self.keymap_list.is_empty() {
+            self.keymap_list = keymap_list(Path::new(KEYMAPS));
+            self.logs.push(Log::new("log_keymaps_detect", &[&self.keymap_list.len().to_string()]));
+        }
+        if self.layout_list.is_empty() {
+            self.layout_list = layout_list();
+            self.logs.push(Log::new("log_layouts_detect", &[&self.layout_list.len().to_string()]));
+        }
+    }
+    pub fn set_keyboard_default(&mut self) {
+        self.keyboard = Keyboard::new();
+        self.keyboard.keymap = self.target_language.get("keymap");
+        self.keyboard.layouts = self.target_language.get("layouts").split(',').map(split_layout).collect();
+    }
+    pub fn keyboard_items(&self) -> Vec<String> {
+        match self.keyboard_picker {
+            Some(KeyboardPicker::Keymap) => search(self.keymap_list.iter(), &self.keyboard_search),
+            Some(KeyboardPicker::Layout) => {
+                let layouts: Vec<String> = self.layout_list.iter()
+                    .map(|layout| format!("{}  {}", layout.0, layout.1))
+                    .collect();
+                search(layouts.iter(), &self.keyboard_search)
+            }
+            None => Vec::new(),
+        }
+    }
+    pub fn keyboard_select(&mut self) {
+        if let Some(item) = self.keyboard_items().get(self.select_num) {
+            match self.keyboard_picker.clone().unwrap_or_else(|| self.language.fail("error_state_keyboard_picker", &[])) {
+                KeyboardPicker::Keymap => {
+                    self.keyboard.keymap = item.clone();
+                    self.load_keymap();
+                    self.select_num = 0;
+                }
+                KeyboardPicker::Layout => {
+                    let code = item.split_whitespace().next().unwrap_or_else(|| self.language.fail("error_state_layout", &[]));
+                    let layout = split_layout(code);
+                    if !self.keyboard.layouts.contains(&layout) {
+                        self.keyboard.layouts.push(layout);
                     }
+                    self.logs.push(Log::new("log_layouts", &[&self.keyboard.layout_names()]));
+                    self.select_num = 1;
                 }
             }
         }
+        self.keyboard_picker = None;
+        self.keyboard_search.clear();
+    }
+    pub fn load_keymap(&mut self) {
+        let status = Command::new("loadkeys")
+            .arg(&self.keyboard.keymap)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false);
+        if status {
+            self.logs.push(Log::new("log_keymap_load", &[&self.keyboard.keymap]));
+        } else {
+            self.logs.push(Log::new("log_keymap_load_failed", &[&self.keyboard.keymap]));
+        }
+    }
+
+    pub fn set_timezone_list(&mut self) {
+        if self.timezone_list.is_empty() {
+            self.timezone_list = zone_list(Path::new(ZONEINFO));
+            self.logs.push(Log::new("log_timezones_detect", &[&self.timezone_list.len().to_string()]));
+        }
+
+        if self.timezone_list.is_empty() {
+            self.language.fail("error_no_timezones", &[])
+        }
+    }
+    pub fn timezone_items(&self) -> Vec<String> {
+        match &self.timezone_region {
+            None => search(self.timezone_list.iter().map(|region| &region.0), &self.timezone_search),
+            Some(region) => {
+                let cities = &self.timezone_list.iter()
+                    .find(|item| &item.0 == region)
+                    .unwrap_or_else(|| self.language.fail("error_state_timezone_region", &[]))
+                    .1;
+                search(cities.iter(), &self.timezone_search)
+            }
+        }
+    }
+    // Returns true once a city or a zone without cities is picked, a region only opens its city list
+    pub fn timezone_select(&mut self) -> bool {
+        let Some(item) = self.timezone_items().get(self.select_num).cloned() else {
+            return false;
+        };
+        self.timezone_search.clear();
+        self.select_num = 0;
+
+        match self.timezone_region.clone() {
+            None if self.timezone_list.iter().any(|(region, cities)| region == &item && cities.is_empty()) => {
+                self.timezone = item;
+                self.logs.push(Log::new("log_timezone_select", &[&self.timezone]));
+                true
+            }
+            None => {
+                self.timezone_region = Some(item);
+                false
+            }
+            Some(region) => {
+                self.timezone = region + "/" + &item;
+                self.logs.push(Log::new("log_timezone_select", &[&self.timezone]));
+                true
+            }
+        }
+    }
+
+    pub fn set_locale_list(&mut self) {
+        if self.locale_list.is_empty() {
+            self.locale_list = locale_list();
+            self.logs.push(Log::new("log_locales_detect", &[&self.locale_list.len().to_string()]));
+        }
+
+        if self.locale_list.is_empty() {
+            self.language.fail("error_no_locales", &[])
+        }
+    }
+    pub fn locale_items(&self) -> Vec<String> {
+        let names: Vec<String> = self.locale_list.iter().map(|locale| locale.0.clone()).collect();
+        search(names.iter(), &self.locale_search)
+    }
+    pub fn locale_select(&mut self) {
+        let category = self.locale_category.unwrap_or_else(|| self.language.fail("error_state_locale_category", &[]));
+
+        if let Some(item) = self.locale_items().get(self.select_num) {
+            self.locale_values[category] = item.clone();
+            self.logs.push(Log::new("log_locale_select", &[CATEGORIES[category], item]));
+        }
+        self.locale_category = None;
+        self.locale_search.clear();
+        self.select_num = category;
+    }
+
+    pub fn edit_user(&mut self, index: Option<usize>) {
+        self.user = match index {
+            Some(index) => self.users.get(index).unwrap_or_else(|| self.language.fail("error_state_user", &[])).clone(),
+            None => User::new(),
+        };
+        self.user_index = index;
+        self.password_repeat = self.user.password.clone();
+        self.group_select = 0;
+        self.editing = Some(Editing::Name);
+        self.screen = Screen::UserSetup;
+    }
+    pub fn user_name_check(&self) -> Result<(), NameError> {
+        validate_username(&self.user.name)?;
+
+        let taken = self.users.iter()
+            .enumerate()
+            .any(|(index, user)| user.name == self.user.name && Some(index) != self.user_index);
+        if taken {
+            return Err(NameError::Taken);
+        }
+        Ok(())
+    }
+    pub fn save_user(&mut self) {
+        match self.user_index {
+            Some(index) => self.users[index] = self.user.clone(),
+            None => self.users.push(self.user.clone()),
+        }
+        self.logs.push(Log::new("log_user_save", &[&self.user.name]));
+
+        self.editing = None;
+        self.screen = Screen::UserList;
+        self.select_num = self.user_index.unwrap_or(self.users.len() - 1);
+    }
+
+
+    pub fn wifi_check(&mut self) -> bool {
+        self.connectivity = check(&self.probe_targets, PROBE_TIMEOUT, &self.proxy);
+        self.connectivity == Connectivity::Online
+    }
+    pub fn set_interfaces(&mut self) {
+        self.interfaces = interface_list(Path::new(SYS_NET));
+        for interface in self.interfaces.iter_mut() {
+            interface.address = address(&interface.name);
+            self.logs.push(Log::new("log_interface_detect", &[&interface.name]).with_key(interface.link.name()));
+        }
+        if self.interfaces.is_empty() {
+            self.language.fail("error_no_network_device", &[])
+        }
+    }
+    // Only a machine with nothing but Wi-Fi goes straight to the network list
+    pub fn wifi_only(&self) -> bool {
+        self.interfaces.iter().all(|interface| interface.link == Link::Wifi)
+    }
+    pub fn interface_select(&mut self) {
+        let interface = self.interfaces.get(self.select_num)
+            .unwrap_or_else(|| self.language.fail("error_state_interface", &[]))
+            .clone();
+        self.wifi_device = interface.name.clone();
+        self.logs.push(Log::new("log_network_device", &[&interface.name]));
+
+        if interface.link == Link::Wifi {
+            self.screen = Screen::WifiSelection;
+            self.select_num = 0;
+            self.set_wifi_list();
+            return;
+        }
+
+        self.wifi = Data::new();
+        self.wifi.name = interface.name.clone();
+        self.wifi_profile = None;
+        self.wifi_thread = Some(thread::spawn(move || dhcp(&interface.name)));
+        self.wifi_status = WifiStatus::Connecting(Instant::now());
+        self.logs.push(Log::new("log_dhcp", &[&self.wifi.name]));
+    }
+
+    pub fn manual_open(&mut self) {
+        let Some(interface) = self.interfaces.get(self.select_num) else {
+            return;
+        };
+        if interface.link == Link::Wifi {
+            return;
+        }
+        if self.manual.as_ref().is_none_or(|manual| manual.device != interface.name) {
+            self.manual = Some(Manual::new(&interface.name));
+        }
+        self.manual_field = Some(ManualField::Address);
+        self.wifi_status = WifiStatus::Idle;
+    }
+    pub fn manual_input(&mut self) -> Option<&mut String> {
+        let manual = self.manual.as_mut()?;
+        match self.manual_field.as_ref()? {
+            ManualField::Address => Some(&mut manual.address),
+            ManualField::Gateway => Some(&mut manual.gateway),
+            ManualField::Dns => Some(&mut manual.dns),
+            ManualField::Proxy => Some(&mut manual.proxy),
+            ManualField::Persist => None,
+        }
+    }
+    // Applied right away, the connectivity check then runs like after DHCP
+    pub fn manual_apply(&mut self) {
+        let manual = self.manual.clone().unwrap_or_else(|| self.language.fail("error_state_manual_network", &[]));
+        match apply_manual(&manual) {
+            Ok(()) => {}
+            Err(NetworkError::Auth) => return self.wifi_failed(Log::new("wifi_error_auth", &[])),
+            Err(NetworkError::Failed(text)) => return self.wifi_failed(Log::new("wifi_error_failed", &[&text])),
+            Err(error) => self.language.network_fail(&error),
+        }
+        self.logs.push(Log::new("log_manual_apply", &[&manual.address, &manual.device]));
+
+        self.proxy = manual.proxy.clone();
+        if !manual.proxy.is_empty() {
+            self.logs.push(Log::new("log_proxy", &[&manual.proxy]));
+        }
+
+        self.manual_field = None;
+        self.wifi_profile = None;
+        self.wifi_device = manual.device.clone();
+        self.wifi.name = manual.device.clone();
+        self.wifi_thread = None;
+        self.wifi_status = WifiStatus::Connecting(Instant::now());
+    }
+
+    pub fn set_wifi_list(&mut self) {
+        if self.network.is_none() {
+            let network = detect_backend().unwrap_or_else(|| self.language.fail("error_no_network_backend", &[]));
+            self.logs.push(Log::new("log_network_backend", &[network.name()]));
+            self.network = Some(network);
+        }
+        let network = self.network.as_ref().unwrap_or_else(|| self.language.fail("error_state_network_backend", &[]));
 
         if self.wifi_device == String::new() {
-            panic!("failed to get network device")
+            let devices = network.devices().unwrap_or_else(|error| self.language.network_fail(&error));
+            if let Some(device) = devices.first() {
+                self.logs.push(Log::new("log_network_device", &[device]));
+                self.wifi_device = device.clone();
+            }
         }
 
-        let output = Command::new("iwctl")
-            .args(["station", &self.wifi_device, "get-networks"])
-            .output()
-            .expect("failed to run iwctl");
-        let wifi_list_text =  String::from_utf8_lossy(&output.stdout);
+        if self.wifi_device == String::new() {
+            self.language.fail("error_no_network_device", &[])
+        }
 
-        self.wifi_list.clear();
+        self.wifi_list = network.networks(&self.wifi_device).unwrap_or_else(|error| self.language.network_fail(&error));
+        for wifi in self.wifi_list.iter() {
+            self.logs.push(Log::new("log_wifi_detect", &[&wifi.name]));
+        }
+    }
+    // The entry after the scanned networks is for a hidden one
+    pub fn wifi_hidden(&self) -> bool {
+        self.select_num >= self.wifi_list.len()
+    }
+    pub fn wifi_security(&self) -> Security {
+        match self.wifi_list.get(self.select_num) {
+            Some(network) => network.security.clone(),
+            None => hidden_security(&self.wifi.password),
+        }
+    }
+    pub fn wifi_fields(&self) -> Vec<WifiField> {
+        if self.wifi_hidden() {
+            return vec![WifiField::Ssid, WifiField::Password];
+        }
+        match self.wifi_security() {
+            Security::Open => Vec::new(),
+            Security::Psk => vec![WifiField::Password],
+            Security::Enterprise => vec![WifiField::Eap, WifiField::Identity, WifiField::Password, WifiField::CaCert],
+        }
+    }
+    pub fn wifi_input(&mut self, field: &WifiField) -> Option<&mut String> {
+        match field {
+            WifiField::Ssid => Some(&mut self.wifi.name),
+            WifiField::Eap => None,
+            WifiField::Identity => Some(&mut self.wifi_enterprise.identity),
+            WifiField::Password if self.wifi_security() == Security::Enterprise => Some(&mut self.wifi_enterprise.password),
+            WifiField::Password => Some(&mut self.wifi.password),
+            WifiField::CaCert => Some(&mut self.wifi_enterprise.ca_cert),
+        }
+    }
+    pub fn wifi_move(&mut self, forward: bool) {
+        let fields = self.wifi_fields();
+        let Some(index) = fields.iter().position(|field| Some(field) == self.wifi_field.as_ref()) else {
+            return;
+        };
+        let index = if forward { (index + 1).min(fields.len() - 1) } else { index.saturating_sub(1) };
+        self.wifi_field = Some(fields[index].clone());
+    }
 
-        for wifi_text in wifi_list_text.lines().skip(3) {
-            if let Some((_, after)) = wifi_text.split_once("      ") {
-                if let Some((before, _)) = after.split_once(" ") {
-                    self.logs.push("WiFi detect: ".to_string() + before);
+    // Opens the form the network needs, open networks need none
+    pub fn wifi_select(&mut self) {
+        self.wifi = Data::new();
+        self.wifi_enterprise = Enterprise::new();
+        self.wifi_status = WifiStatus::Idle;
+        if let Some(network) = self.wifi_list.get(self.select_num) {
+            self.wifi.name = network.name.clone();
+            self.logs.push(Log::new("log_network_select", &[&self.wifi.name]));
+        }
+        self.wifi_field = self.wifi_fields().first().cloned();
+    }
+    pub fn wifi_rescan(&mut self) {
+        let network = self.network.clone().unwrap_or_else(|| self.language.fail("error_state_network_backend", &[]));
+        network.scan(&self.wifi_device).unwrap_or_else(|error| self.language.network_fail(&error));
+        self.set_wifi_list();
+        self.select_num = self.select_num.min(self.wifi_list.len());
+        self.wifi_status = WifiStatus::Idle;
+    }
 
-                    self.wifi_list.push(before.to_string());
+    // Connecting blocks for seconds, so it runs in a thread and wifi_tick watches it
+    pub fn wifi_connect(&mut self) {
+        let network = self.network.clone().unwrap_or_else(|| self.language.fail("error_state_network_backend", &[]));
+        let device = self.wifi_device.clone();
+        let name = self.wifi.name.clone();
+        let password = self.wifi.password.clone();
+        let enterprise = self.wifi_enterprise.clone();
+        let hidden = self.wifi_hidden();
+        let security = self.wifi_security();
+
+        self.wifi_profile = Some(WifiProfile {
+            ssid: name.clone(),
+            security: security.clone(),
+            hidden,
+            psk: password.clone(),
+            enterprise: enterprise.clone(),
+        });
+        self.wifi_thread = Some(thread::spawn(move || {
+            if hidden {
+                network.connect_hidden(&device, &name, &password)
+            } else if security == Security::Enterprise {
+                network.connect_enterprise(&device, &name, &enterprise)
+            } else {
+                network.connect(&device, &name, &password)
+            }
+        }));
+        self.wifi_status = WifiStatus::Connecting(Instant::now());
+        self.logs.push(Log::new("log_wifi_connect", &[&self.wifi.name]));
+    }
+    pub fn wifi_connecting(&self) -> bool {
+        matches!(self.wifi_status, WifiStatus::Connecting(_))
+    }
+    pub fn wifi_cancel(&mut self) {
+        // The thread is left to finish on its own, its result is no longer needed
+        self.wifi_thread = None;
+        self.probe_thread = None;
+        self.wifi_profile = None;
+        self.wifi_status = WifiStatus::Idle;
+    }
+
+    // Called while connecting, true once there is internet
+    pub fn wifi_tick(&mut self) -> bool {
+        let WifiStatus::Connecting(started) = self.wifi_status else {
+            return false;
+        };
+
+        if self.wifi_thread.as_ref().is_some_and(|thread| thread.is_finished()) {
+            let thread = self.wifi_thread.take().unwrap_or_else(|| self.language.fail("error_state_wifi_thread", &[]));
+            let result = thread.join().unwrap_or_else(|_| self.language.fail("error_state_wifi_thread", &[]));
+            match result {
+                Ok(()) => {}
+                Err(NetworkError::Auth) => {
+                    self.wifi_failed(Log::new("wifi_error_auth", &[]));
+                    return false;
+                }
+                Err(NetworkError::Failed(text)) => {
+                    self.wifi_failed(Log::new("wifi_error_failed", &[&text]));
+                    return false;
                 }
+                Err(error) => self.language.network_fail(&error),
+            }
+        }
+
+        // The probe waits for curl, so it runs aside to keep the spinner going
+        if self.probe_thread.as_ref().is_some_and(|thread| thread.is_finished()) {
+            let thread = self.probe_thread.take().unwrap_or_else(|| self.language.fail("error_state_probe_thread", &[]));
+            self.connectivity = thread.join().unwrap_or_else(|_| self.language.fail("error_state_probe_thread", &[]));
+            if self.connectivity == Connectivity::Online {
+                self.wifi_status = WifiStatus::Idle;
+                self.wifi_field = None;
+                return true;
             }
         }
+        if self.wifi_thread.is_none() && self.probe_thread.is_none() {
+            let targets = self.probe_targets.clone();
+            let proxy = self.proxy.clone();
+            self.probe_thread = Some(thread::spawn(move || check(&targets, PROBE_TIMEOUT, &proxy)));
+        }
+
+        if started.elapsed() >= WIFI_TIMEOUT {
+            self.wifi_thread = None;
+            self.probe_thread = None;
+            let log = self.connectivity_log();
+            self.wifi_failed(log);
+        }
+        false
     }
-    pub fn wifi_connect(&mut self) {
-        Command::new("iwctl")
-            .args(["station", &self.wifi_device, "connect", &self.wifi.name, &self.wifi.password])
-            .output()
-            .expect("failed to run iwctl");
-        self.logs.push("Wifi connect to ".to_string() + &self.wifi.name);
+    // The last probe says more than a timeout, unless there is no address at all
+    fn connectivity_log(&self) -> Log {
+        if !has_address(&self.wifi_device) {
+            return Log::new("wifi_error_dhcp", &[]);
+        }
+        match &self.connectivity {
+            Connectivity::DnsFailure => Log::new("connectivity_dns", &[]),
+            Connectivity::NoRoute => Log::new("connectivity_no_route", &[&self.probe_targets.join(", ")]),
+            Connectivity::CaptivePortal(url) if url.is_empty() => Log::new("connectivity_tls", &[]),
+            Connectivity::CaptivePortal(url) => Log::new("connectivity_captive_portal", &[url]),
+            Connectivity::Failed(text) if !text.is_empty() => Log::new("wifi_error_failed", &[text]),
+            _ => Log::new("wifi_error_timeout", &[]),
+        }
+    }
+    pub fn wifi_failed(&mut self, log: Log) {
+        self.wifi_profile = None;
+        self.logs.push(log.clone());
+        self.wifi_status = WifiStatus::Failed(log);
     }
+    // A wrong clock makes pacman reject the signatures, so the sync is watched until it is done
     pub fn time_sync(&mut self) {
-        let output = Command::new("timedatectl").output().expect("failed to run timedatectl");
-        let time_sync_check = String::from_utf8_lossy(&output.stdout);
-        let time_sync_check = time_sync_check.find("System clock synchronized: yes");
-
-        if let None = time_sync_check {
-            fs::write("/etc/systemd/timesyncd.conf", "
-[Time]
-NTP=time.cloudflare.com time.google.com
-FallbackNTP=time.cloudflare.com time.google.com 0.arch.pool.ntp.org 1.arch.pool.ntp.org 2.arch.pool.ntp.org 3.arch.pool.ntp.org
-").expect("Timesync conf save error");
-            self.logs.push("Timesync save conf".to_string());
-
-            Command::new("systemctl")
-                .args(["restart", "systemd-timesyncd.service"])
-                .output()
-                .expect("failed to restart timesyncd");
-            self.logs.push("Timesyncd to restart".to_string());
+        let synced = synchronized().unwrap_or_else(|| self.language.fail("error_run", &["timedatectl"]));
+        if synced {
+            self.time_status = TimeStatus::Synced;
+            self.logs.push(Log::new("log_timesync_done", &[]));
+            return;
+        }
+
+        fs::write(TIMESYNCD_CONF, timesyncd_conf(&self.ntp_servers)).unwrap_or_else(|_| self.language.fail("error_save", &[TIMESYNCD_CONF]));
+        self.logs.push(Log::new("log_timesync_save", &[]));
+
+        Command::new("timedatectl")
+            .args(["set-ntp", "true"])
+            .output()
+            .unwrap_or_else(|_| self.language.fail("error_run", &["timedatectl"]));
+        Command::new("systemctl")
+            .args(["restart", "systemd-timesyncd.service"])
+            .output()
+            .unwrap_or_else(|_| self.language.fail("error_run", &["systemctl"]));
+        self.logs.push(Log::new("log_timesync_restart", &[]));
+        self.time_status = TimeStatus::Syncing(Instant::now());
+    }
+    pub fn time_syncing(&self) -> bool {
+        matches!(self.time_status, TimeStatus::Syncing(_))
+    }
+    pub fn time_tick(&mut self) {
+        let TimeStatus::Syncing(started) = self.time_status else {
+            return;
+        };
+        if synchronized().unwrap_or(false) {
+            self.time_status = TimeStatus::Synced;
+            self.logs.push(Log::new("log_timesync_done", &[]));
+        } else if started.elapsed() >= TIME_SYNC_TIMEOUT {
+            self.time_status = TimeStatus::Failed;
+            self.logs.push(Log::new("log_timesync_failed", &[]));
+        }
+    }
+    // Signatures may fail with a wrong clock, so installing without a sync takes a second Enter
+    pub fn install_ready(&mut self) -> bool {
+        match self.time_status {
+            TimeStatus::Synced => true,
+            TimeStatus::Syncing(_) => false,
+            TimeStatus::Idle | TimeStatus::Failed => {
+                let ready = self.time_confirmed;
+                self.time_confirmed = true;
+                ready
+            }
         }
     }
 
 
+    pub fn set_mirror_list(&mut self) {
+        if self.mirror_list.is_empty() {
+            let text = fs::read_to_string(MIRRORLIST).unwrap_or_default();
+            self.mirror_list = parse_mirrorlist(&text);
+            self.logs.push(Log::new("log_mirrors_detect", &[&self.mirror_list.len().to_string()]));
+
+            // Without countries to pick from the full list is fetched, the screen stays empty if that fails
+            if countries(&self.mirror_list).is_empty() {
+                let fetched = fetch_mirrorlist(FETCH_TIMEOUT, &self.proxy).map(|text| parse_mirrorlist(&text)).unwrap_or_default();
+                if countries(&fetched).is_empty() {
+                    self.logs.push(Log::new("log_mirrors_fetch_failed", &[MIRRORLIST_ALL]));
+                } else {
+                    self.logs.push(Log::new("log_mirrors_fetch", &[MIRRORLIST_ALL, &fetched.len().to_string()]));
+                    self.mirror_list = fetched;
+                }
+            }
+        }
+    }
+    pub fn mirror_items(&self) -> Vec<String> {
+        search(countries(&self.mirror_list).iter(), &self.mirror_search)
+    }
+    pub fn mirror_rank(&mut self) {
+        let Some(country) = self.mirror_country.clone() else {
+            return;
+        };
+        let candidates: Vec<Mirror> = self.mirror_list.iter()
+            .filter(|mirror| mirror.country == country)
+            .cloned()
+            .collect();
+        let ranked = rank(&candidates, RANK_TIMEOUT, &self.proxy);
+        self.logs.push(Log::new("log_mirrors_rank", &[&country, &ranked.len().to_string()]));
+        self.mirror_ranked = Some(ranked);
+    }
+    // pacstrap reads the live list, the target gets the same one in complite
+    pub fn mirror_save(&mut self) {
+        let mirrors: Vec<Mirror> = self.mirror_ranked.iter().flatten().map(|(mirror, _)| mirror.clone()).collect();
+        fs::write(MIRRORLIST, mirrorlist(&mirrors)).unwrap_or_else(|_| self.language.fail("error_save", &[MIRRORLIST]));
+        self.logs.push(Log::new("log_mirrors_save", &[&mirrors.len().to_string()]));
+        self.mirror_chosen = mirrors;
+    }
+
     pub fn set_disk_list(&mut self) {
         if self.disk_list.len() == 0 {
             let output = Command::new("lsblk")
                 .arg("-J")
                 .output()
-                .expect("failed to run lsblk");
+                .unwrap_or_else(|_| self.language.fail("error_run", &["lsblk"]));
             let disk_list_text = String::from_utf8_lossy(&output.stdout);
 
             let json: serde_json::Value = serde_json::from_str(&disk_list_text)
-                .expect("Json parse disk list error");
+                .unwrap_or_else(|_| self.language.fail("error_parse", &["lsblk"]));
 
             let disk_list = json.get("blockdevices")
-                .expect("Error to get \"blockdevices\" of disk list");
+                .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]));
             let disk_list = disk_list.as_array()
-                .expect("Error \"blockdevices\" is not array");
+                .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]));
 
 
             let mut disk_num: usize = 0;
 
             for disk in disk_list {
                 let disk_name = disk.get("name")
-                    .expect("Error get name is disk")
+                    .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]))
                     .as_str()
-                    .expect("Error disk name is not str");
+                    .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]));
 
                 self.disk_list.push((disk_name.to_string(), Vec::new()));
-                self.logs.push("Disk detect: ".to_string() + &disk_name);
+                self.logs.push(Log::new("log_disk_detect", &[&disk_name]));
 
 
                 if let Some(part_list) = disk.get("children") {
                     let part_list = part_list.as_array()
-                        .expect("Error parts is not array");
+                        .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]));
 
                     for part in part_list {
                         let part_name = part.get("name")
-                            .expect("Error get name is part")
+                            .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]))
                             .as_str()
-                            .expect("Error part name is not str");
+                            .unwrap_or_else(|| self.language.fail("error_parse", &["lsblk"]));
 
                         self.disk_list.get_mut(disk_num)
-                                .expect("fatal of self disk list")
+                                .unwrap_or_else(|| self.language.fail("error_state_disk_list", &[]))
                                 .1.push(part_name.to_string());
-                        self.logs.push("Part detect: ".to_string() + &part_name);
+                        self.logs.push(Log::new("log_part_detect", &[&part_name]));
                     }
                 }
 
@@ -208,18 +955,18 @@ FallbackNTP=time.cloudflare.com time.google.com 0.arch.pool.ntp.org 1.arch.pool.
         }
 
         if self.disk_list.len() == 0 {
-            panic!("failed to get disk list")
+            self.language.fail("error_no_disks", &[])
         }
     }
 
     pub fn efi_check(&mut self) -> bool {
         match fs::read("/sys/firmware/efi/fw_platform_size") {
             Ok(_) => {
-                self.logs.push("Efi is true".to_string());
+                self.logs.push(Log::new("log_efi_true", &[]));
                 true
             }
             Err(_) => {
-                self.logs.push("Efi is false".to_string());
+                self.logs.push(Log::new("log_efi_false", &[]));
                 true
             }
         }
@@ -229,46 +976,46 @@ FallbackNTP=time.cloudflare.com time.google.com 0.arch.pool.ntp.org 1.arch.pool.
         Command::new("mkfs.ext4")
             .arg("/dev/".to_string() + &self.disk_tom)
             .output()
-            .expect(&("Failed to format ext4: ".to_string() + &self.disk_tom));
-        self.logs.push("Format to ext4: ".to_string() + &self.disk_tom);
+            .unwrap_or_else(|_| self.language.fail("error_format", &[&self.disk_tom]));
+        self.logs.push(Log::new("log_format_ext4", &[&self.disk_tom]));
         Command::new("mount")
             .args(["/dev/".to_string() + &self.disk_tom, "/mnt".to_string()])
             .output()
-            .expect(&("Failed to mount ext4: ".to_string() + &self.disk_tom));
-        self.logs.push("Mount to /mnt: /dev/".to_string() + &self.disk_tom);
+            .unwrap_or_else(|_| self.language.fail("error_mount", &[&self.disk_tom]));
+        self.logs.push(Log::new("log_mount_root", &[&self.disk_tom]));
 
 
         if let Some(part) = &self.disk_tom_efi {
             Command::new("mkfs.vfat")
                 .arg("/dev/".to_string() + part)
                 .output()
-                .expect(&format!("failed to format part {} for efi", part));
-            self.logs.push("Format to vfat: ".to_string() + &part);
+                .unwrap_or_else(|_| self.language.fail("error_format", &[part]));
+            self.logs.push(Log::new("log_format_vfat", &[&part]));
             Command::new("mkdir")
                 .args(["-p", "/mnt/boot/efi"])
                 .output()
-                .expect("failed create efi path");
+                .unwrap_or_else(|_| self.language.fail("error_create", &["/mnt/boot/efi"]));
             Command::new("mount")
                 .args(["/dev/".to_string() + &part, "/mnt/boot/efi".to_string()])
                 .output()
-                .expect(&("Failed to mount ext4: ".to_string() + &self.disk_tom));
-            self.logs.push("Mount to /mnt/boot/efi: /dev/".to_string() + &self.disk_tom);
+                .unwrap_or_else(|_| self.language.fail("error_mount", &[&self.disk_tom]));
+            self.logs.push(Log::new("log_mount_efi", &[&self.disk_tom]));
         }
     }
 
     pub fn set_install_list(&mut self) {
-        self.install_list.push(("Linux".to_string(), vec![
+        self.install_list.push(("install_linux".to_string(), vec![
             "base".to_string(),
             "base-devel".to_string(),
             "linux".to_string(),
             "linux-firmware".to_string(),
         ]));
-        self.install_list.push(("Display drivers".to_string(), vec![
+        self.install_list.push(("install_display".to_string(), vec![
             "wayland".to_string(),
             "xorg-xwayland".to_string(),
             "brightnessctl".to_string(),
         ]));
-        self.install_list.push(("Audio drivers".to_string(), vec![
+        self.install_list.push(("install_audio".to_string(), vec![
             "pipewire".to_string(),
             "pipewire-alsa".to_string(),
             "pipewire-jack".to_string(),
@@ -282,7 +1029,7 @@ FallbackNTP=time.cloudflare.com time.google.com 0.arch.pool.ntp.org 1.arch.pool.
         let output = Command::new("lspci")
             .arg("-mm")
             .output()
-            .expect("failed to run lspci");
+            .unwrap_or_else(|_| self.language.fail("error_run", &["lspci"]));
         let gpus_text = String::from_utf8_lossy(&output.stdout).to_lowercase();
 
         let mut nvidia_gpu = false;
@@ -307,35 +1054,35 @@ FallbackNTP=time.cloudflare.com time.google.com 0.arch.pool.ntp.org 1.arch.pool.
         }
 
         if nvidia_gpu {
-            self.install_list.push(("Nvidia gpu drivers".to_string(), vec![
+            self.install_list.push(("install_nvidia".to_string(), vec![
                 "nvidia".to_string(),
                 "nvidia-utils".to_string(),
                 "nvidia-setting".to_string(),
             ]));
-            self.logs.push("Nvidia gpu detect".to_string());
+            self.logs.push(Log::new("log_nvidia_gpu", &[]));
         }
         if amd_gpu {
-            self.install_list.push(("Amd gpu drivers".to_string(), vec![
+            self.install_list.push(("install_amd_gpu".to_string(), vec![
                 "vulkan-radeon".to_string(),
             ]));
-            self.logs.push("Amd gpu detect".to_string());
+            self.logs.push(Log::new("log_amd_gpu", &[]));
         }
         if intel_gpu {
-            self.install_list.push(("Amd gpu drivers".to_string(), vec![
+            self.install_list.push(("install_intel_gpu".to_string(), vec![
                 "vulkan-intel".to_string(),
             ]));
-            self.logs.push("Amd gpu detect".to_string());
+            self.logs.push(Log::new("log_intel_gpu", &[]));
         }
         if amd_gpu || intel_gpu {
-            self.install_list.push(("Mesa drivers".to_string(), vec![
+            self.install_list.push(("install_mesa".to_string(), vec![
                 "mesa".to_string(),
             ]));
         }
         if intel_audio {
-            self.install_list.push(("Intel audio drivers".to_string(), vec![
+            self.install_list.push(("install_intel_audio".to_string(), vec![
                 "sof-firmware".to_string(),
             ]));
-            self.logs.push("Intel audio detect".to_string());
+            self.logs.push(Log::new("log_intel_audio", &[]));
         }
 
 
@@ -344,157 +1091,424 @@ FallbackNTP=time.cloudflare.com time.google.com 0.arch.pool.ntp.org 1.arch.pool.
         let intel_cpu = output.contains("genuineintel");
 
         if amd_cpu {
-            self.install_list.push(("Amd cpu drivers".to_string(), vec![
+            self.install_list.push(("install_amd_cpu".to_string(), vec![
                 "amd-ucode".to_string(),
             ]));
-            self.logs.push("Amd cpu detect".to_string());
+            self.logs.push(Log::new("log_amd_cpu", &[]));
         }
         if intel_cpu {
-            self.install_list.push(("Intel cpu drivers".to_string(), vec![
+            self.install_list.push(("install_intel_cpu".to_string(), vec![
                 "intel-ucode".to_string(),
             ]));
-            self.logs.push("Intel cpu detect".to_string());
+            self.logs.push(Log::new("log_intel_cpu", &[]));
         }
 
 
-        self.install_list.push(("Working environment".to_string(), vec![
+        self.install_list.push(("install_desktop".to_string(), vec![
             "hyprland".to_string(),
             "sddm".to_string(),
             "grub".to_string(),
         ]));
         if let Some(_) = self.disk_tom_efi {
-            self.install_list.push(("Efi boot loader".to_string(), vec![
+            self.install_list.push(("install_efi".to_string(), vec![
                 "efibootmgr".to_string(),
             ]));
-            self.logs.push("Efi detect".to_string());
+            self.logs.push(Log::new("log_efi_detect", &[]));
         }
 
-        self.install_list.push(("Default apps".to_string(), vec![
+        self.install_list.push(("install_apps".to_string(), vec![
             "nano".to_string(),
             "wget".to_string(),
             "sudo".to_string(),
             "networkmanager".to_string(),
         ]));
-        self.install_list.push(("Fonts".to_string(), vec![
+
+        let mut shell_packages = Vec::<String>::new();
+        for user in self.users.iter() {
+            for package in user.shell.packages() {
+                if !shell_packages.contains(&package) {
+                    shell_packages.push(package);
+                }
+            }
+        }
+        if !shell_packages.is_empty() {
+            self.install_list.push(("install_shells".to_string(), shell_packages));
+        }
+
+        self.install_list.push(("install_fonts".to_string(), vec![
             "ttf-ubuntu-font-family".to_string(),
             "ttf-hack".to_string(),
             "ttf-dejavu".to_string(),
             "ttf-opensans".to_string(),
         ]));
+
+        // Languages with their own script name the font and input method engine packages in the translation
+        let fonts = self.target_language.get("fonts");
+        if !fonts.is_empty() {
+            self.install_list.push(("install_language_fonts".to_string(), fonts.split_whitespace().map(String::from).collect()));
+        }
+        let input_method = self.target_language.get("input_method");
+        if !input_method.is_empty() {
+            self.install_list.push(("install_input_method".to_string(), vec![
+                "fcitx5".to_string(),
+                "fcitx5-gtk".to_string(),
+                "fcitx5-qt".to_string(),
+                "fcitx5-configtool".to_string(),
+                input_method,
+            ]));
+        }
+    }
+
+    // Old ISOs miss keys of newer packagers and pacstrap then rejects their signatures
+    pub fn keyring_preflight(&mut self) {
+        run_step("pacman-key", &["--init"], "").unwrap_or_else(|error| self.language.fail("error_keyring_init", &[&error]));
+        self.logs.push(Log::new("log_keyring_init", &[]));
+
+        // Populated first, the new keyring package is signed by the keys it replaces
+        run_step("pacman-key", &["--populate", "archlinux"], "").unwrap_or_else(|error| self.language.fail("error_keyring_populate", &[&error]));
+        self.logs.push(Log::new("log_keyring_populate", &[]));
+
+        // The age is only as good as the clock
+        if !matches!(self.time_status, TimeStatus::Synced) {
+            self.logs.push(Log::new("log_keyring_clock", &[]));
+        }
+        if let Some(age) = iso_date().and_then(|built| keyring_age(built, today())) {
+            self.logs.push(Log::new("log_keyring_outdated", &[&age.to_string()]));
+            run_step("pacman", &["-Sy", "--noconfirm", "archlinux-keyring"], &self.proxy)
+                .unwrap_or_else(|error| self.language.fail("error_keyring_update", &[&error]));
+            self.logs.push(Log::new("log_keyring_update", &[]));
+        }
     }
 
     pub fn install(&mut self) {
         let install_package = self.install_list
             .get(self.select_num)
-            .expect("Error select install list")
+            .unwrap_or_else(|| self.language.fail("error_state_install_list", &[]))
             .clone();
 
         let mut install_list = vec!["/mnt".to_string()];
         install_list.extend(install_package.1);
 
-        Command::new("pacstrap")
+        let output = set_proxy(&mut Command::new("pacstrap"), &self.proxy)
             .args(install_list)
             .output()
-            .expect(&("Failed install: ".to_string() + &install_package.0));
-        self.logs.push("Install: ".to_string() + &install_package.0);
+            .unwrap_or_else(|_| self.language.fail("error_install", &[&self.language.get(&install_package.0)]));
+        // The last lines name the package, a signature error is otherwise lost in the download log
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let lines: Vec<&str> = stderr.lines().rev().take(3).collect();
+            let lines: Vec<&str> = lines.into_iter().rev().collect();
+            self.language.fail("error_command", &["pacstrap", &lines.join("\n")]);
+        }
+        self.logs.push(Log::new("log_install", &[]).with_key(&install_package.0));
+    }
+
+    pub fn set_mirrorlist(&mut self) {
+        if self.mirror_chosen.is_empty() {
+            return;
+        }
+        let path = format!("/mnt{}", MIRRORLIST);
+        fs::write(&path, mirrorlist(&self.mirror_chosen)).unwrap_or_else(|_| self.language.fail("error_save", &[&path]));
+    }
+
+    pub fn set_hostname(&mut self) {
+        fs::write("/mnt/etc/hostname", format!("{}\n", self.hostname))
+            .unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/hostname"]));
+        fs::write("/mnt/etc/hosts", hosts_file(&self.hostname))
+            .unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/hosts"]));
+        self.logs.push(Log::new("log_hostname_save", &[&self.hostname]));
+    }
+
+    pub fn set_timezone(&mut self) {
+        let zone = format!("{}/{}", ZONEINFO, self.timezone);
+        let output = self.chroot(&["ln", "-sf", &zone, "/etc/localtime"]);
+        if !output.status.success() {
+            self.language.fail("error_timezone", &[&self.timezone, &String::from_utf8_lossy(&output.stderr)]);
+        }
+        self.logs.push(Log::new("log_timezone_save", &[&self.timezone]));
+
+        let mut args = vec!["hwclock", "--systohc"];
+        if self.rtc_local {
+            args.push("--localtime");
+        } else {
+            args.push("--utc");
+        }
+        let output = self.chroot(&args);
+        if !output.status.success() {
+            self.language.fail("error_command", &["hwclock", &String::from_utf8_lossy(&output.stderr)]);
+        }
+        self.logs.push(Log::new("log_hwclock", &[]));
+    }
+
+    pub fn set_time_sync(&mut self) {
+        let path = format!("/mnt{}", TIMESYNCD_CONF);
+        fs::write(&path, timesyncd_conf(&self.ntp_servers)).unwrap_or_else(|_| self.language.fail("error_save", &[&path]));
+        self.chroot(&["systemctl", "enable", "systemd-timesyncd"]);
+        self.logs.push(Log::new("log_timesync_enable", &[&self.ntp_servers]));
+    }
+
+    pub fn set_locale(&mut self) {
+        // en_US stays generated as a fallback for programs without a translation
+        let mut names = vec!["en_US.UTF-8".to_string()];
+        for value in self.locale_values.iter() {
+            if !value.is_empty() && !names.contains(value) {
+                names.push(value.clone());
+            }
+        }
+        let locales: Vec<(String, String)> = names.iter()
+            .filter_map(|name| self.locale_list.iter().find(|locale| &locale.0 == name).cloned())
+            .collect();
+
+        fs::write("/mnt/etc/locale.gen", locale_gen(&locales)).unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/locale.gen"]));
+        self.logs.push(Log::new("log_locale_gen_save", &[]));
+
+        fs::write("/mnt/etc/locale.conf", locale_conf(&self.locale_values))
+            .unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/locale.conf"]));
+        self.logs.push(Log::new("log_locale_conf_save", &[]));
+
+        let output = self.chroot(&["locale-gen"]);
+        if !output.status.success() {
+            self.language.fail("error_command", &["locale-gen", &String::from_utf8_lossy(&output.stderr)]);
+        }
+        self.logs.push(Log::new("log_locale_gen", &[]));
+    }
+
+    pub fn set_keyboard(&mut self) {
+        let font = self.target_language.get("console_font");
+        fs::write("/mnt/etc/vconsole.conf", vconsole_conf(&self.keyboard, &font))
+            .unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/vconsole.conf"]));
+        self.logs.push(Log::new("log_keymap_save", &[&self.keyboard.keymap]));
+        self.logs.push(Log::new("log_font_save", &[&font]));
+
+        fs::create_dir_all("/mnt/etc/X11/xorg.conf.d").unwrap_or_else(|_| self.language.fail("error_create", &["/mnt/etc/X11/xorg.conf.d"]));
+        fs::write("/mnt/etc/X11/xorg.conf.d/00-keyboard.conf", xorg_keyboard_conf(&self.keyboard))
+            .unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/X11/xorg.conf.d/00-keyboard.conf"]));
+        self.logs.push(Log::new("log_xkb_save", &[&self.keyboard.layout_names()]));
+    }
+
+    pub fn set_hyprland(&mut self) {
+        let base = fs::read_to_string(DEFAULT_CONFIG).unwrap_or_default();
+        let input_method = !self.target_language.get("input_method").is_empty();
+        let config = hyprland_conf(&base, &self.keyboard, input_method);
+
+        for user in self.users.clone() {
+            let dir = format!("/mnt/home/{}/.config/hypr", user.name);
+            fs::create_dir_all(&dir).unwrap_or_else(|_| self.language.fail("error_create", &[&dir]));
+            fs::write(dir.clone() + "/hyprland.conf", &config).unwrap_or_else(|_| self.language.fail("error_save", &["hyprland.conf"]));
+
+            let owner = format!("{}:{}", user.name, user.name);
+            let home = format!("/home/{}/.config", user.name);
+            self.chroot(&["chown", "-R", &owner, &home]);
+            self.logs.push(Log::new("log_hyprland_save", &[&user.name]));
+        }
+    }
+
+    // Static addresses typed during the install, when they were asked to be kept
+    pub fn set_network(&mut self) {
+        let Some(manual) = self.manual.clone() else {
+            return;
+        };
+
+        match manual.persist {
+            Persist::No => return,
+            Persist::NetworkManager => {
+                let dir = "/mnt/etc/NetworkManager/system-connections";
+                let path = format!("{}/{}.nmconnection", dir, manual.device);
+                fs::create_dir_all(dir).unwrap_or_else(|_| self.language.fail("error_create", &[dir]));
+                fs::write(&path, nm_manual_keyfile(&manual)).unwrap_or_else(|_| self.language.fail("error_save", &[&path]));
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+                    .unwrap_or_else(|_| self.language.fail("error_permissions", &[&path]));
+            }
+            Persist::Networkd => {
+                let dir = "/mnt/etc/systemd/network";
+                let path = format!("{}/20-{}.network", dir, manual.device);
+                fs::create_dir_all(dir).unwrap_or_else(|_| self.language.fail("error_create", &[dir]));
+                fs::write(&path, networkd_conf(&manual)).unwrap_or_else(|_| self.language.fail("error_save", &[&path]));
+
+                // NetworkManager stays for Wi-Fi but must leave this interface alone
+                let dir = "/mnt/etc/NetworkManager/conf.d";
+                let path = format!("{}/20-unmanaged-{}.conf", dir, manual.device);
+                fs::create_dir_all(dir).unwrap_or_else(|_| self.language.fail("error_create", &[dir]));
+                fs::write(&path, format!("[keyfile]\nunmanaged-devices=interface-name:{}\n", manual.device))
+                    .unwrap_or_else(|_| self.language.fail("error_save", &[&path]));
+
+                self.chroot(&["systemctl", "enable", "systemd-networkd", "systemd-resolved"]);
+            }
+        }
+        self.logs.push(Log::new("log_network_save", &[manual.persist.name()]));
+
+        if !manual.proxy.is_empty() {
+            let mut environment = fs::read_to_string("/mnt/etc/environment").unwrap_or_default();
+            environment += &proxy_environment(&manual.proxy);
+            fs::write("/mnt/etc/environment", environment).unwrap_or_else(|_| self.language.fail("error_save", &["/mnt/etc/environment"]));
+        }
+    }
+
+    // iwd hashes the passphrase into its profile, that profile is what actually connected
+    pub fn set_wifi_profile(&mut self) {
+        let Some(mut profile) = self.wifi_profile.clone() else {
+            return;
+        };
+        if self.network.as_ref().is_some_and(|network| network.name() == "iwd") {
+            let path = format!("{}/{}", IWD_DIR, iwd_profile_name(&profile.ssid, &profile.security));
+            if let Ok(text) = fs::read_to_string(&path) {
+                profile = profile_from_iwd(&profile, &text);
+            }
+        }
+
+        if let Some(source) = move_ca_cert(&mut profile) {
+            let dir = "/mnt".to_string() + NM_CERTS;
+            let target = "/mnt".to_string() + &profile.enterprise.ca_cert;
+            fs::create_dir_all(&dir).unwrap_or_else(|_| self.language.fail("error_create", &[&dir]));
+            fs::copy(&source, &target).unwrap_or_else(|_| self.language.fail("error_copy", &[&source]));
+            fs::set_permissions(&target, fs::Permissions::from_mode(0o600))
+                .unwrap_or_else(|_| self.language.fail("error_permissions", &[&target]));
+        }
+
+        let dir = "/mnt/etc/NetworkManager/system-connections";
+        let path = format!("{}/{}", dir, nm_keyfile_name(&profile.ssid));
+        fs::create_dir_all(dir).unwrap_or_else(|_| self.language.fail("error_create", &[dir]));
+        fs::write(&path, nm_wifi_keyfile(&profile)).unwrap_or_else(|_| self.language.fail("error_save", &[&path]));
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+            .unwrap_or_else(|_| self.language.fail("error_permissions", &[&path]));
+        self.logs.push(Log::new("log_wifi_save", &[&profile.ssid]));
+    }
+
+    pub fn chroot(&self, args: &[&str]) -> Output {
+        Command::new("arch-chroot")
+            .arg("/mnt")
+            .args(args)
+            .output()
+            .unwrap_or_else(|_| self.language.fail("error_run", &[&("arch-chroot /mnt ".to_string() + &args.join(" "))]))
+    }
+
+    pub fn add_users(&mut self) {
+        for user in self.users.clone() {
+            for group in user.groups.iter() {
+                self.chroot(&["groupadd", "-f", group]);
+            }
+
+            let groups = user.all_groups().join(",");
+            let shell = user.shell.path();
+            let mut args = vec!["useradd", "-m", "-s", &shell];
+            if !groups.is_empty() {
+                args.extend(["-G", &groups]);
+            }
+            args.push(&user.name);
+
+            let output = self.chroot(&args);
+            if !output.status.success() {
+                self.language.fail("error_add_user", &[&user.name, &String::from_utf8_lossy(&output.stderr)]);
+            }
+            self.logs.push(Log::new("log_user_add", &[&user.name]));
+        }
+
+        // Installing needs an administrator, root shares the password of the first one
+        let root = self.users.iter()
+            .find(|user| user.admin)
+            .unwrap_or_else(|| self.language.fail("error_state_admin", &[]));
+
+        let mut passwords = String::new();
+        for user in self.users.iter() {
+            passwords += &format!("{}:{}\n", user.name, user.password);
+        }
+        passwords += &format!("root:{}\n", root.password);
+
+        let mut chpasswd = Command::new("arch-chroot")
+            .args(["/mnt", "chpasswd"])
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|_| self.language.fail("error_run", &["chpasswd"]));
+        chpasswd.stdin.take()
+            .unwrap_or_else(|| self.language.fail("error_passwords", &[]))
+            .write_all(passwords.as_bytes())
+            .unwrap_or_else(|_| self.language.fail("error_passwords", &[]));
+        if !chpasswd.wait().unwrap_or_else(|_| self.language.fail("error_run", &["chpasswd"])).success() {
+            self.language.fail("error_passwords", &[]);
+        }
+        self.logs.push(Log::new("log_passwords", &[]));
+        self.logs.push(Log::new("log_root", &[&root.name]));
+    }
+
+    pub fn set_sudo(&mut self) {
+        let path = "/mnt/etc/sudoers.d/10-santana";
+
+        fs::create_dir_all("/mnt/etc/sudoers.d").unwrap_or_else(|_| self.language.fail("error_create", &["/mnt/etc/sudoers.d"]));
+        fs::write(path, "%wheel ALL=(ALL:ALL) ALL\n").unwrap_or_else(|_| self.language.fail("error_save", &[path]));
+        fs::set_permissions(path, fs::Permissions::from_mode(0o440))
+            .unwrap_or_else(|_| self.language.fail("error_permissions", &[path]));
+
+        let output = self.chroot(&["visudo", "-cf", "/etc/sudoers.d/10-santana"]);
+        if !output.status.success() {
+            fs::remove_file(path).unwrap_or_else(|_| self.language.fail("error_remove", &[path]));
+            // visudo -c reports syntax errors on stderr
+            let text = String::from_utf8_lossy(&output.stderr).to_string() + &String::from_utf8_lossy(&output.stdout);
+            self.language.fail("error_sudoers_invalid", &[text.trim()]);
+        }
+        self.logs.push(Log::new("log_sudo", &[]));
     }
 
     pub fn complite(&mut self) {
         Command::new("genfstab")
             .args(["/mnt", ">>", "/mnt/etc/fstab"])
             .output()
-            .expect("Failed run genfstab");
-        self.logs.push("Genfstab create for /mnt/etc/fstab".to_string());
+            .unwrap_or_else(|_| self.language.fail("error_run", &["genfstab"]));
+        self.logs.push(Log::new("log_genfstab", &[]));
 
-        Command::new("arch-chroot")
-            .args(["/mnt"])
-            .output()
-            .expect("Failed run arch-chroot /mnt");
-        Command::new("systemctl")
-            .args(["enable", "NetworkManager"])
-            .output()
-            .expect("Failed enable NetwrokManager for systemctl");
-        self.logs.push("System enable NetworkManager".to_string());
+        self.set_hostname();
+        self.set_mirrorlist();
+        self.set_timezone();
+        self.set_time_sync();
+
+        self.chroot(&["systemctl", "enable", "NetworkManager"]);
+        self.logs.push(Log::new("log_enable_networkmanager", &[]));
+        self.set_network();
+        self.set_wifi_profile();
 
         Command::new("systemctl")
             .args(["enable", "sddm"])
             .output()
-            .expect("Failed enable sddm for systemctl");
-        self.logs.push("System enable sddm".to_string());
+            .unwrap_or_else(|_| self.language.fail("error_run", &["systemctl"]));
+        self.logs.push(Log::new("log_enable_sddm", &[]));
 
 
-        Command::new("useradd")
-            .args(["-m", &self.user.name])
-            .output()
-            .expect("Failed enable sddm for systemctl");
-        self.logs.push("System add user".to_string());
+        self.add_users();
+        self.set_sudo();
+        self.set_keyboard();
+        self.set_hyprland();
 
-        Command::new("echo")
-            .args([&format!("\"{}:{}\"", self.user.name, self.user.password), "|", "chpasswd"])
-            .output()
-            .expect("Failed set password for chpasswd");
-        self.logs.push("System set password".to_string());
 
-        Command::new("echo")
-            .args([&format!("\"root:{}\"", self.user.password), "|", "chpasswd"])
-            .output()
-            .expect("Failed set root password for chpasswd");
-        self.logs.push("System set root".to_string());
-
-        let mut file = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open("/etc/sudoers")
-            .expect("filed to edit file: /etc/sudoers");
-        writeln!(file, "{} ALL=(ALL:ALL) ALL", self.user.name)
-            .expect("Error to add user to file /etc/sudoers");
-        self.logs.push("System set root for user".to_string());
-
-
-        fs::write("/etc/locale.gen", format!("
-en_US.UTF-8 UTF-8
-{}
-", self.language.locale)).expect("Languages save error");
-        self.logs.push("Languages save".to_string());
-
-        fs::write("/etc/locale.conf", "LANG=\"en_US.UTF-8\"")
-            .expect("Language conf save error");
-        self.logs.push("Language conf save".to_string());
-
-        Command::new("locale-gen")
-            .output()
-            .expect("Failed to run locale-gen");
-        self.logs.push("Locale generation succes!".to_string());
+        self.set_locale();
 
         let disk_name = self.disk_list
             .get(self.disk)
-            .expect("Error to part list for grub-install")
+            .unwrap_or_else(|| self.language.fail("error_state_disk", &[]))
             .0.clone();
 
         Command::new("grub-install")
             .arg("/dev/".to_string() + &disk_name)
             .output()
-            .expect("Failed to run grub-install");
-        self.logs.push("Grub install succes!".to_string());
+            .unwrap_or_else(|_| self.language.fail("error_run", &["grub-install"]));
+        self.logs.push(Log::new("log_grub_install", &[]));
 
         Command::new("grub-mkconfig")
             .args(["-o", "/boot/grub/grub.cfg"])
             .output()
-            .expect("Failed to run grub-mkconfig");
-        self.logs.push("Grub config to set".to_string());
+            .unwrap_or_else(|_| self.language.fail("error_run", &["grub-mkconfig"]));
+        self.logs.push(Log::new("log_grub_config", &[]));
 
         Command::new("exit")
             .output()
-            .expect("Failed to exit for arch-chroot");
+            .unwrap_or_else(|_| self.language.fail("error_run", &["exit"]));
 
         Command::new("umount")
             .args(["-R", "/mnt"])
             .output()
-            .expect("Failed to umount /mnt");
-        self.logs.push("Umount /mnt".to_string());
+            .unwrap_or_else(|_| self.language.fail("error_run", &["umount"]));
+        self.logs.push(Log::new("log_umount", &[]));
 
         Command::new("reboot")
             .output()
-            .expect("Failed to reboot");
+            .unwrap_or_else(|_| self.language.fail("error_run", &["reboot"]));
     }
 }
\ No newline at end of file
@@ -0,0 +1,150 @@
+use std::{fs, process::Command, time::Duration};
+
+use crate::{mirror::MIRRORLIST, network::set_proxy};
+
+
+pub const FALLBACK_TARGET: &str = "https://archlinux.org";
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const CMDLINE_PARAM: &str = "santana.check=";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Connectivity {
+    Online,
+    DnsFailure,
+    NoRoute,
+    Timeout,
+    // Where the portal wanted to send us, empty when it broke TLS instead
+    CaptivePortal(String),
+    Failed(String),
+}
+
+// The database of core, so the check also tells the mirror serves packages
+pub fn first_mirror(mirrorlist: &str) -> Option<String> {
+    let server = mirrorlist.lines().find_map(|line| {
+        let (key, value) = line.trim().split_once('=')?;
+        (key.trim() == "Server").then(|| value.trim())
+    })?;
+    Some(format!("{}/core.db", server.replace("$repo", "core").replace("$arch", "x86_64")))
+}
+
+// Targets from the kernel parameter, comma separated, win over the mirrorlist
+pub fn probe_targets(cmdline: &str, mirrorlist: &str) -> Vec<String> {
+    if let Some(value) = cmdline.split_whitespace().find_map(|param| param.strip_prefix(CMDLINE_PARAM)) {
+        let targets: Vec<String> = value.split(',').filter(|target| !target.is_empty()).map(String::from).collect();
+        if !targets.is_empty() {
+            return targets;
+        }
+    }
+    vec![first_mirror(mirrorlist).unwrap_or(FALLBACK_TARGET.to_string())]
+}
+
+pub fn detect_targets() -> Vec<String> {
+    let cmdline = fs::read_to_string("/proc/cmdline").unwrap_or_default();
+    let mirrorlist = fs::read_to_string(MIRRORLIST).unwrap_or_default();
+    probe_targets(&cmdline, &mirrorlist)
+}
+
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    authority.rsplit_once('@').map_or(authority, |(_, host)| host)
+}
+
+// Answers from the mirror count as online even for errors, redirects elsewhere do not
+fn classify(url: &str, code: u16, redirect: &str) -> Connectivity {
+    match code {
+        511 => Connectivity::CaptivePortal(redirect.to_string()),
+        300..=399 if !redirect.is_empty() && host(redirect) != host(url) => Connectivity::CaptivePortal(redirect.to_string()),
+        100..=599 => Connectivity::Online,
+        _ => Connectivity::Failed(code.to_string()),
+    }
+}
+
+pub fn probe(url: &str, timeout: Duration, proxy: &str) -> Connectivity {
+    let output = set_proxy(&mut Command::new("curl"), proxy)
+        .args(["--silent", "--show-error", "--head", "--output", "/dev/null"])
+        .args(["--max-time", &timeout.as_secs().max(1).to_string()])
+        .args(["--write-out", "%{http_code} %{redirect_url}"])
+        .arg(url)
+        .output();
+    let Ok(output) = output else {
+        return Connectivity::Failed("curl".to_string());
+    };
+
+    // Exit codes from curl(1)
+    match output.status.code() {
+        Some(0) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let (code, redirect) = stdout.split_once(' ').unwrap_or((&stdout, ""));
+            classify(url, code.trim().parse().unwrap_or(0), redirect.trim())
+        }
+        Some(6) => Connectivity::DnsFailure,
+        Some(7) => Connectivity::NoRoute,
+        Some(28) => Connectivity::Timeout,
+        // A certificate that does not match usually means someone answers for the mirror
+        Some(35 | 51 | 58 | 60) => Connectivity::CaptivePortal(String::new()),
+        _ => Connectivity::Failed(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    }
+}
+
+// Online if any target answers, otherwise what went wrong with the first one
+pub fn check(targets: &[String], timeout: Duration, proxy: &str) -> Connectivity {
+    let mut first = None;
+    for target in targets {
+        let result = probe(target, timeout, proxy);
+        if result == Connectivity::Online {
+            return result;
+        }
+        first.get_or_insert(result);
+    }
+    first.unwrap_or(Connectivity::Failed(String::new()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in::{stand_in, OK};
+    use std::net::TcpListener;
+
+    fn mirror(response: Option<&'static str>, delay: Duration) -> String {
+        format!("http://127.0.0.1:{}/core/os/x86_64/core.db", stand_in(response, delay))
+    }
+
+    #[test]
+    fn targets() {
+        let mirrorlist = "## Germany\n#Server = https://old.example/$repo/os/$arch\nServer = https://mirror.example/archlinux/$repo/os/$arch\n";
+        assert_eq!(probe_targets("", mirrorlist), vec!["https://mirror.example/archlinux/core/os/x86_64/core.db"]);
+        assert_eq!(probe_targets("quiet santana.check=http://a.example,https://b.example", mirrorlist), vec!["http://a.example", "https://b.example"]);
+        assert_eq!(probe_targets("quiet", "## nothing here\n"), vec![FALLBACK_TARGET]);
+    }
+
+    #[test]
+    fn probe_states() {
+        let online = mirror(Some(OK), Duration::ZERO);
+        assert_eq!(probe(&online, PROBE_TIMEOUT, ""), Connectivity::Online);
+
+        let portal = mirror(Some("HTTP/1.1 302 Found\r\nLocation: http://portal.example/login\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"), Duration::ZERO);
+        assert_eq!(probe(&portal, PROBE_TIMEOUT, ""), Connectivity::CaptivePortal("http://portal.example/login".to_string()));
+
+        let portal = mirror(Some("HTTP/1.1 511 Network Authentication Required\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"), Duration::ZERO);
+        assert_eq!(probe(&portal, PROBE_TIMEOUT, ""), Connectivity::CaptivePortal(String::new()));
+
+        let silent = mirror(None, Duration::from_secs(3));
+        assert_eq!(probe(&silent, Duration::from_secs(1), ""), Connectivity::Timeout);
+
+        // Nothing listens on the port once the listener is gone
+        let port = TcpListener::bind("127.0.0.1:0").expect("Cannot bind").local_addr().expect("No address").port();
+        assert_eq!(probe(&format!("http://127.0.0.1:{}/", port), PROBE_TIMEOUT, ""), Connectivity::NoRoute);
+
+        assert_eq!(probe("http://mirror.invalid/", PROBE_TIMEOUT, ""), Connectivity::DnsFailure);
+    }
+
+    #[test]
+    fn check_any_target() {
+        let online = mirror(Some(OK), Duration::ZERO);
+        let targets = vec!["http://mirror.invalid/".to_string(), online];
+        assert_eq!(check(&targets, PROBE_TIMEOUT, ""), Connectivity::Online);
+        assert_eq!(check(&targets[..1], PROBE_TIMEOUT, ""), Connectivity::DnsFailure);
+    }
+}
@@ -0,0 +1,44 @@
+#[derive(Clone, PartialEq)]
+pub enum HostnameError {
+    Empty,
+    TooLong,
+    LabelLength,
+    InvalidChar,
+    HyphenEdge,
+}
+
+// RFC 1123: dot separated labels of 1-63 letters, digits and hyphens, 253 characters at most
+pub fn validate_hostname(hostname: &str) -> Result<(), HostnameError> {
+    if hostname.is_empty() {
+        return Err(HostnameError::Empty);
+    }
+    if hostname.len() > 253 {
+        return Err(HostnameError::TooLong);
+    }
+
+    for label in hostname.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(HostnameError::LabelLength);
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(HostnameError::InvalidChar);
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(HostnameError::HyphenEdge);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn hosts_file(hostname: &str) -> String {
+    let aliases = match hostname.split_once('.') {
+        Some((short, _)) => format!("{} {}", hostname, short),
+        None => hostname.to_string(),
+    };
+
+    format!("127.0.0.1\tlocalhost
+::1\t\tlocalhost
+127.0.1.1\t{}
+", aliases)
+}
@@ -0,0 +1,39 @@
+use crate::keyboard::Keyboard;
+
+
+pub const DEFAULT_CONFIG: &str = "/mnt/usr/share/hypr/hyprland.conf";
+
+
+// The example config shipped with hyprland, without the autogenerated warning, plus our settings.
+// Later blocks win in hyprland, so the appended input block overrides the example one.
+pub fn hyprland_conf(base: &str, keyboard: &Keyboard, input_method: bool) -> String {
+    let mut text = String::new();
+    for line in base.lines() {
+        if !line.trim_start().starts_with("autogenerated") {
+            text += line;
+            text += "\n";
+        }
+    }
+
+    text += &format!("
+# Generated by santana
+input {{
+    kb_layout = {}
+    kb_variant = {}
+    kb_options = {}
+}}
+", keyboard.layout_names(), keyboard.variant_names(), keyboard.options());
+
+    // fcitx5 has to be started with the session and announced to every toolkit
+    if input_method {
+        text += "
+env = XMODIFIERS,@im=fcitx
+env = QT_IM_MODULE,fcitx
+env = GTK_IM_MODULE,fcitx
+env = SDL_IM_MODULE,fcitx
+exec-once = fcitx5 -d
+";
+    }
+
+    text
+}
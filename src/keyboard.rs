@@ -0,0 +1,162 @@
+use std::{fs, path::Path};
+
+
+pub const KEYMAPS: &str = "/usr/share/kbd/keymaps";
+pub const XKB_RULES: &str = "/usr/share/X11/xkb/rules/base.lst";
+
+pub const TOGGLES: [&str; 5] = [
+    "grp:alt_shift_toggle",
+    "grp:ctrl_shift_toggle",
+    "grp:win_space_toggle",
+    "grp:caps_toggle",
+    "",
+];
+
+
+#[derive(Clone)]
+pub struct Keyboard {
+    pub keymap: String,
+    pub layouts: Vec<(String, String)>,
+    pub toggle: String,
+}
+impl Keyboard {
+    pub fn new() -> Self {
+        Self {
+            keymap: "us".to_string(),
+            layouts: vec![("us".to_string(), String::new())],
+            toggle: TOGGLES[0].to_string(),
+        }
+    }
+
+    pub fn layout_names(&self) -> String {
+        self.layouts.iter().map(|layout| layout.0.clone()).collect::<Vec<String>>().join(",")
+    }
+    pub fn variant_names(&self) -> String {
+        self.layouts.iter().map(|layout| layout.1.clone()).collect::<Vec<String>>().join(",")
+    }
+    pub fn options(&self) -> String {
+        if self.layouts.len() > 1 {
+            self.toggle.clone()
+        } else {
+            String::new()
+        }
+    }
+}
+
+
+fn collect_keymaps(dir: &Path, keymaps: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if path.is_dir() {
+            if name != "include" {
+                collect_keymaps(&path, keymaps);
+            }
+        } else if let Some(keymap) = name.strip_suffix(".map.gz").or(name.strip_suffix(".map")) {
+            keymaps.push(keymap.to_string());
+        }
+    }
+}
+
+pub fn keymap_list(root: &Path) -> Vec<String> {
+    let mut keymaps = Vec::new();
+    collect_keymaps(root, &mut keymaps);
+    keymaps.sort();
+    keymaps.dedup();
+    keymaps
+}
+
+// Layouts as "ru" and variants as "ru(phonetic)" from the "! layout" and "! variant" sections
+pub fn parse_base_lst(text: &str) -> Vec<(String, String)> {
+    let mut layouts = Vec::new();
+    let mut section = "";
+
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix("! ") {
+            section = name.trim();
+            continue;
+        }
+
+        let line = line.trim();
+        let Some((code, description)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let description = description.trim();
+
+        match section {
+            "layout" => layouts.push((code.to_string(), description.to_string())),
+            "variant" => if let Some((layout, description)) = description.split_once(": ") {
+                layouts.push((format!("{}({})", layout, code), description.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    layouts
+}
+
+pub fn layout_list() -> Vec<(String, String)> {
+    let mut layouts = parse_base_lst(&fs::read_to_string(XKB_RULES).unwrap_or_default());
+    layouts.sort();
+    layouts
+}
+
+pub fn split_layout(item: &str) -> (String, String) {
+    match item.split_once('(') {
+        Some((layout, variant)) => (layout.to_string(), variant.trim_end_matches(')').to_string()),
+        None => (item.to_string(), String::new()),
+    }
+}
+
+pub fn vconsole_conf(keyboard: &Keyboard, font: &str) -> String {
+    let mut text = format!("KEYMAP={}\n", keyboard.keymap);
+    if !font.is_empty() {
+        text += &format!("FONT={}\n", font);
+    }
+    text
+}
+
+pub fn xorg_keyboard_conf(keyboard: &Keyboard) -> String {
+    format!("Section \"InputClass\"
+        Identifier \"system-keyboard\"
+        MatchIsKeyboard \"on\"
+        Option \"XkbLayout\" \"{}\"
+        Option \"XkbVariant\" \"{}\"
+        Option \"XkbOptions\" \"{}\"
+EndSection
+", keyboard.layout_names(), keyboard.variant_names(), keyboard.options())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(code: &str, description: &str) -> (String, String) {
+        (code.to_string(), description.to_string())
+    }
+
+    #[test]
+    fn layouts() {
+        assert_eq!(parse_base_lst(include_str!("../fixtures/keyboard/base.lst")), vec![
+            layout("us", "English (US)"),
+            layout("de", "German"),
+            layout("ru", "Russian"),
+            layout("us(intl)", "English (US, intl., with dead keys)"),
+            layout("de(nodeadkeys)", "German (no dead keys)"),
+            layout("ru(phonetic)", "Russian (phonetic)"),
+        ]);
+        assert_eq!(split_layout("ru(phonetic)"), ("ru".to_string(), "phonetic".to_string()));
+    }
+
+    #[test]
+    fn keymaps() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/keyboard/keymaps");
+        assert_eq!(keymap_list(&root), vec!["de-latin1", "mac-us", "ru", "us"]);
+    }
+}
@@ -0,0 +1,99 @@
+use std::{fs, process::Command, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::network::set_proxy;
+
+
+// archiso writes its build date here, like 2024.06.01
+pub const ISO_VERSION: &str = "/version";
+// Keys of packagers added after this many days are likely missing from the ISO
+pub const KEYRING_MAX_AGE_DAYS: i64 = 60;
+
+// Days since 1970-01-01 for a date of the proleptic Gregorian calendar
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn parse_date(year: &str, month: &str, day: &str) -> Option<i64> {
+    let (year, month, day) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+pub fn parse_iso_date(text: &str) -> Option<i64> {
+    let mut parts = text.trim().split('.');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    parse_date(year, month, day)
+}
+
+// `pacman -Q archlinux-keyring` prints the version, which is the release date
+pub fn parse_keyring_date(text: &str) -> Option<i64> {
+    let version = text.split_whitespace().nth(1)?;
+    let date = version.split('-').next()?;
+    if date.len() != 8 {
+        return None;
+    }
+    parse_date(&date[0..4], &date[4..6], &date[6..8])
+}
+
+pub fn today() -> i64 {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    seconds as i64 / 86400
+}
+
+// The ISO date, or the keyring it shipped with when booted from something else
+pub fn iso_date() -> Option<i64> {
+    if let Some(date) = fs::read_to_string(ISO_VERSION).ok().and_then(|text| parse_iso_date(&text)) {
+        return Some(date);
+    }
+    let output = Command::new("pacman").args(["-Q", "archlinux-keyring"]).output().ok()?;
+    parse_keyring_date(&String::from_utf8_lossy(&output.stdout))
+}
+
+pub fn keyring_age(built: i64, today: i64) -> Option<i64> {
+    let age = today - built;
+    (age > KEYRING_MAX_AGE_DAYS).then_some(age)
+}
+
+// Err carries what the command printed, pacman-key explains itself on stderr
+pub fn run_step(program: &str, args: &[&str], proxy: &str) -> Result<(), String> {
+    let output = set_proxy(&mut Command::new(program), proxy).args(args).output().map_err(|error| format!("{}: {}", program, error))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        Err(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(stderr)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(parse_iso_date("2024.06.01\n"), Some(days_from_civil(2024, 6, 1)));
+        assert_eq!(parse_iso_date("latest"), None);
+        assert_eq!(parse_keyring_date("archlinux-keyring 20240520-1\n"), Some(days_from_civil(2024, 5, 20)));
+        assert_eq!(parse_keyring_date("error: package 'archlinux-keyring' was not found"), None);
+    }
+
+    #[test]
+    fn outdated() {
+        let built = days_from_civil(2024, 6, 1);
+        assert_eq!(keyring_age(built, built + KEYRING_MAX_AGE_DAYS), None);
+        assert_eq!(keyring_age(built, built + 200), Some(200));
+    }
+}
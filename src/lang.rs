@@ -1,59 +1,255 @@
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use crate::{host::HostnameError, network::{ManualError, NetworkError}, user::NameError};
+
+
+// Built into the binary, the first one is the fallback for missing keys
+const TRANSLATIONS: [(&str, &str); 10] = [
+    ("en", include_str!("../lang/en.json")),
+    ("ru", include_str!("../lang/ru.json")),
+    ("de", include_str!("../lang/de.json")),
+    ("fr", include_str!("../lang/fr.json")),
+    ("es", include_str!("../lang/es.json")),
+    ("uk", include_str!("../lang/uk.json")),
+    ("pt", include_str!("../lang/pt.json")),
+    ("zh", include_str!("../lang/zh.json")),
+    ("ja", include_str!("../lang/ja.json")),
+    ("ko", include_str!("../lang/ko.json")),
+];
+
+// Files here replace keys of a built-in language with the same name or add a new language
+pub const OVERRIDE_DIR: &str = "/etc/santana/lang";
+
+// santana.lang=de on the kernel command line picks the language of a localized ISO
+const CMDLINE_PARAM: &str = "santana.lang=";
+
+
 #[derive(Clone)]
 pub struct Lang {
-    pub _name: String,
-    pub locale: String,
-    pub full_name: String,
-    pub screen_language: String,
-    pub screen_wifi_select: String,
-    pub screen_wifi_password: String,
-    pub screen_disk_select: String,
-    pub screen_part_select: String,
-    pub screen_part_select_for_efi: String,
-    pub screen_usersetup: String,
-    pub screen_usersetup_name: String,
-    pub screen_usersetup_pass: String,
-    pub screen_installing: String,
+    pub name: String,
+    strings: HashMap<String, String>,
 }
 
 impl Lang {
-    pub fn en() -> Self {
+    pub fn parse(text: &str) -> Option<HashMap<String, String>> {
+        let json: serde_json::Value = serde_json::from_str(text).ok()?;
+
+        let mut strings = HashMap::new();
+        for (key, value) in json.as_object()? {
+            strings.insert(key.clone(), value.as_str()?.to_string());
+        }
+        Some(strings)
+    }
+
+    fn new(name: &str, fallback: &HashMap<String, String>, strings: HashMap<String, String>) -> Self {
+        let mut merged = fallback.clone();
+        merged.extend(strings);
+
         Self {
-            _name: "en".to_string(),
-            locale: "".to_string(),
-            full_name: "English".to_string(),
-            screen_language: "Select language".to_string(),
-            screen_wifi_select: "Select network".to_string(),
-            screen_wifi_password: "Enter password".to_string(),
-            screen_disk_select: "Select disk".to_string(),
-            screen_part_select: "Select part".to_string(),
-            screen_part_select_for_efi: "Select part for efi".to_string(),
-            screen_usersetup: "Create user".to_string(),
-            screen_usersetup_name: "Name".to_string(),
-            screen_usersetup_pass: "Password".to_string(),
-            screen_installing: "Installing".to_string(),
-        }
-    }
-
-    pub fn ru() -> Self {
+            name: name.to_string(),
+            strings: merged,
+        }
+    }
+
+    pub fn en() -> Self {
+        let strings = Lang::parse(TRANSLATIONS[0].1).expect("Error parse built-in en translation");
         Self {
-            _name: "ru".to_string(),
-            locale: "ru_RU.UTF-8 UTF-8".to_string(),
-            full_name: "Русский".to_string(),
-            screen_language: "Выбор языка".to_string(),
-            screen_wifi_select: "Выбор сети".to_string(),
-            screen_wifi_password: "Введите пароль".to_string(),
-            screen_disk_select: "Выбор диска".to_string(),
-            screen_part_select: "Выбор раздела".to_string(),
-            screen_part_select_for_efi: "Выбор раздела загрузчика".to_string(),
-            screen_usersetup: "Создание пользователя".to_string(),
-            screen_usersetup_name: "Имя".to_string(),
-            screen_usersetup_pass: "Пароль".to_string(),
-            screen_installing: "Установка".to_string(),
+            name: TRANSLATIONS[0].0.to_string(),
+            strings,
         }
     }
+
+    pub fn get(&self, key: &str) -> String {
+        self.strings.get(key).cloned().unwrap_or(key.to_string())
+    }
+
+    // Fills {0}, {1}, ... so translations can reorder the arguments
+    pub fn fmt(&self, key: &str, args: &[impl AsRef<str>]) -> String {
+        let mut text = self.get(key);
+        for (index, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", index), arg.as_ref());
+        }
+        text
+    }
+
+    pub fn fail(&self, key: &str, args: &[&str]) -> ! {
+        panic!("{}", self.fmt(key, args))
+    }
+
+    pub fn network_fail(&self, error: &NetworkError) -> ! {
+        match error {
+            NetworkError::Run(program) => self.fail("error_run", &[program]),
+            NetworkError::Save(path) => self.fail("error_save", &[path]),
+            NetworkError::Auth => self.fail("wifi_error_auth", &[]),
+            NetworkError::Failed(text) => self.fail("wifi_error_failed", &[text]),
+        }
+    }
+
+    pub fn username_error(&self, error: &NameError) -> String {
+        self.get(match error {
+            NameError::Empty => "username_empty",
+            NameError::TooLong => "username_too_long",
+            NameError::InvalidStart => "username_invalid_start",
+            NameError::InvalidChar => "username_invalid_char",
+            NameError::Reserved => "username_reserved",
+            NameError::Taken => "username_taken",
+        })
+    }
+
+    pub fn manual_error(&self, error: &ManualError) -> String {
+        self.get(match error {
+            ManualError::Address => "manual_error_address",
+            ManualError::Gateway => "manual_error_gateway",
+            ManualError::Dns => "manual_error_dns",
+            ManualError::Proxy => "manual_error_proxy",
+        })
+    }
+
+    pub fn hostname_error(&self, error: &HostnameError) -> String {
+        self.get(match error {
+            HostnameError::Empty => "hostname_empty",
+            HostnameError::TooLong => "hostname_too_long",
+            HostnameError::LabelLength => "hostname_label_length",
+            HostnameError::InvalidChar => "hostname_invalid_char",
+            HostnameError::HyphenEdge => "hostname_hyphen_edge",
+        })
+    }
 }
 
 
+fn override_dir() -> PathBuf {
+    env::var("SANTANA_LANG_DIR")
+        .map(PathBuf::from)
+        .unwrap_or(PathBuf::from(OVERRIDE_DIR))
+}
+
+fn read_overrides() -> Vec<(String, HashMap<String, String>)> {
+    let mut overrides = Vec::new();
+
+    let Ok(entries) = fs::read_dir(override_dir()) else {
+        return overrides;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+            continue;
+        };
+
+        if let Some(strings) = fs::read_to_string(&path).ok().and_then(|text| Lang::parse(&text)) {
+            overrides.push((name, strings));
+        }
+    }
+
+    overrides.sort_by(|a, b| a.0.cmp(&b.0));
+    overrides
+}
+
 pub fn get_langs() -> Vec<Lang> {
-    vec![Lang::en(), Lang::ru()]
-}
\ No newline at end of file
+    let fallback = Lang::en().strings;
+    let mut overrides = read_overrides();
+    let mut langs = Vec::new();
+
+    for (name, text) in TRANSLATIONS {
+        let mut strings = Lang::parse(text)
+            .unwrap_or_else(|| panic!("Error parse built-in {} translation", name));
+
+        if let Some(index) = overrides.iter().position(|item| item.0 == name) {
+            strings.extend(overrides.remove(index).1);
+        }
+        langs.push(Lang::new(name, &fallback, strings));
+    }
+
+    for (name, strings) in overrides {
+        langs.push(Lang::new(&name, &fallback, strings));
+    }
+
+    langs
+}
+
+// Matches de_DE.UTF-8 with the locale of a language first and then de with its name
+fn lang_from_locale(langs: &[Lang], value: &str) -> Option<usize> {
+    let value = value.split(['.', '@']).next().unwrap_or_default();
+    if value.is_empty() || value == "C" || value == "POSIX" {
+        return None;
+    }
+
+    langs.iter()
+        .position(|lang| lang.get("locale").split('.').next() == Some(value))
+        .or_else(|| {
+            let code = value.split('_').next().unwrap_or_default();
+            langs.iter().position(|lang| lang.name == code)
+        })
+}
+
+fn lang_from_cmdline(langs: &[Lang], cmdline: &str) -> Option<usize> {
+    let value = cmdline.split_whitespace().find_map(|param| param.strip_prefix(CMDLINE_PARAM))?;
+    langs.iter().position(|lang| lang.name == value).or_else(|| lang_from_locale(langs, value))
+}
+
+fn lang_from_keymap(langs: &[Lang], vconsole: &str) -> Option<usize> {
+    let keymap = vconsole.lines().find_map(|line| line.trim().strip_prefix("KEYMAP="))?;
+    let keymap = keymap.trim_matches('"');
+    langs.iter().position(|lang| lang.get("keymap") == keymap)
+}
+
+// The kernel parameter is set on purpose, so it wins over LANG and the keymap of the live system
+fn lang_from_system(langs: &[Lang], cmdline: &str, lang: &str, vconsole: &str) -> Option<usize> {
+    lang_from_cmdline(langs, cmdline)
+        .or_else(|| lang_from_locale(langs, lang))
+        .or_else(|| lang_from_keymap(langs, vconsole))
+}
+
+pub fn detect_lang(langs: &[Lang]) -> Option<usize> {
+    let cmdline = fs::read_to_string("/proc/cmdline").unwrap_or_default();
+    let vconsole = fs::read_to_string("/etc/vconsole.conf").unwrap_or_default();
+    lang_from_system(langs, &cmdline, &env::var("LANG").unwrap_or_default(), &vconsole)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translations_have_all_en_keys() {
+        let en = Lang::parse(TRANSLATIONS[0].1).expect("en does not parse");
+
+        for (name, text) in TRANSLATIONS {
+            let strings = Lang::parse(text).unwrap_or_else(|| panic!("{} does not parse", name));
+
+            let mut missing: Vec<&String> = en.keys().filter(|key| !strings.contains_key(*key)).collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{} is missing keys: {:?}", name, missing);
+        }
+    }
+
+    #[test]
+    fn detect() {
+        let langs = get_langs();
+        let name = |index: Option<usize>| index.map(|index| langs[index].name.clone());
+
+        assert_eq!(name(lang_from_locale(&langs, "uk_UA.UTF-8")), Some("uk".to_string()));
+        assert_eq!(name(lang_from_locale(&langs, "de_AT.UTF-8@euro")), Some("de".to_string()));
+        assert_eq!(name(lang_from_cmdline(&langs, "quiet santana.lang=fr")), Some("fr".to_string()));
+        assert_eq!(name(lang_from_cmdline(&langs, "quiet santana.lang=pt_BR.UTF-8")), Some("pt".to_string()));
+        assert_eq!(name(lang_from_keymap(&langs, "KEYMAP=de-latin1\nFONT=ter-v16n\n")), Some("de".to_string()));
+        assert_eq!(name(lang_from_keymap(&langs, "KEYMAP=\"ua\"\n")), Some("uk".to_string()));
+
+        assert_eq!(lang_from_locale(&langs, "C.UTF-8"), None);
+        assert_eq!(lang_from_locale(&langs, "xx_XX.UTF-8"), None);
+        assert_eq!(lang_from_cmdline(&langs, "quiet santana.lang=xx"), None);
+        assert_eq!(lang_from_cmdline(&langs, "quiet splash"), None);
+        assert_eq!(lang_from_keymap(&langs, "KEYMAP=dvorak\n"), None);
+        assert_eq!(lang_from_keymap(&langs, ""), None);
+
+        // The kernel parameter beats LANG, which beats the keymap
+        assert_eq!(name(lang_from_system(&langs, "santana.lang=ru", "uk_UA.UTF-8", "KEYMAP=de-latin1")), Some("ru".to_string()));
+        assert_eq!(name(lang_from_system(&langs, "quiet", "uk_UA.UTF-8", "KEYMAP=de-latin1")), Some("uk".to_string()));
+        assert_eq!(name(lang_from_system(&langs, "quiet", "", "KEYMAP=de-latin1")), Some("de".to_string()));
+        assert_eq!(lang_from_system(&langs, "quiet", "C", "KEYMAP=dvorak"), None);
+    }
+}
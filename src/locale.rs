@@ -0,0 +1,85 @@
+use std::fs;
+
+
+pub const SUPPORTED: &str = "/usr/share/i18n/SUPPORTED";
+
+pub const CATEGORIES: [&str; 6] = ["LANG", "LC_TIME", "LC_NUMERIC", "LC_MONETARY", "LC_PAPER", "LC_MEASUREMENT"];
+
+
+// Accepts both the installed "name charset" form and glibc's "name/charset \" source form
+pub fn parse_supported(text: &str) -> Vec<(String, String)> {
+    let mut locales = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim().trim_end_matches('\\').trim();
+        if line.is_empty() || line.starts_with('#') || line.contains('=') {
+            continue;
+        }
+
+        let line = line.replace('/', " ");
+        if let Some((name, charset)) = line.split_once(' ') {
+            locales.push((name.to_string(), charset.trim().to_string()));
+        }
+    }
+
+    locales
+}
+
+pub fn locale_list() -> Vec<(String, String)> {
+    parse_supported(&fs::read_to_string(SUPPORTED).unwrap_or_default())
+}
+
+pub fn locale_gen(locales: &[(String, String)]) -> String {
+    let mut text = String::new();
+    for (name, charset) in locales {
+        text += &format!("{} {}\n", name, charset);
+    }
+    text
+}
+
+pub fn locale_conf(values: &[String]) -> String {
+    let mut text = String::new();
+    for (category, value) in CATEGORIES.iter().zip(values) {
+        if !value.is_empty() {
+            text += &format!("{}={}\n", category, value);
+        }
+    }
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(name: &str, charset: &str) -> (String, String) {
+        (name.to_string(), charset.to_string())
+    }
+
+    #[test]
+    fn supported() {
+        let expected = vec![
+            locale("C.UTF-8", "UTF-8"),
+            locale("de_DE.UTF-8", "UTF-8"),
+            locale("de_DE", "ISO-8859-1"),
+            locale("de_DE@euro", "ISO-8859-15"),
+            locale("en_US.UTF-8", "UTF-8"),
+            locale("en_US", "ISO-8859-1"),
+            locale("ja_JP.EUC-JP", "EUC-JP"),
+            locale("sr_RS@latin", "UTF-8"),
+            locale("uk_UA.UTF-8", "UTF-8"),
+        ];
+        assert_eq!(parse_supported(include_str!("../fixtures/locale/SUPPORTED")), expected);
+        assert_eq!(parse_supported(include_str!("../fixtures/locale/SUPPORTED.glibc")), expected);
+    }
+
+    #[test]
+    fn config() {
+        let locales = vec![locale("en_US.UTF-8", "UTF-8"), locale("de_DE@euro", "ISO-8859-15")];
+        assert_eq!(locale_gen(&locales), "en_US.UTF-8 UTF-8\nde_DE@euro ISO-8859-15\n");
+
+        let values = ["en_US.UTF-8", "de_DE@euro", "", "", "", "uk_UA.UTF-8"].map(String::from);
+        assert_eq!(locale_conf(&values), "LANG=en_US.UTF-8\nLC_TIME=de_DE@euro\nLC_MEASUREMENT=uk_UA.UTF-8\n");
+        assert_eq!(locale_conf(&[]), "");
+    }
+}
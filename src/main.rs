@@ -1,7 +1,27 @@
+use std::time::Duration;
+
 use ratatui::DefaultTerminal;
 use crossterm::event::{self, KeyCode};
 
 mod lang;
+mod connectivity;
+mod host;
+use host::validate_hostname;
+mod hyprland;
+mod keyboard;
+use keyboard::TOGGLES;
+mod keyring;
+mod locale;
+mod mirror;
+use locale::CATEGORIES;
+mod network;
+use network::validate_manual;
+#[cfg(test)]
+mod stand_in;
+mod timesync;
+mod timezone;
+mod user;
+use user::GROUPS;
 
 mod app;
 use app::*;
@@ -30,90 +50,280 @@ fn run(mut terminal: DefaultTerminal) {
     let mut app = App::new();
 
     loop {
-        terminal.draw(|f| ui(f, &app)).expect("Error draw");
+        terminal.draw(|f| ui(f, &app)).unwrap_or_else(|_| app.language.fail("error_draw", &[]));
 
-        if let Some(key) = event::read().expect("Error input").as_key_press_event() {
+        // While connecting or syncing the clock the screen is redrawn for the spinner even without input
+        if (app.wifi_connecting() || app.time_syncing()) && !event::poll(Duration::from_millis(100)).unwrap_or_else(|_| app.language.fail("error_input", &[])) {
+            app.time_tick();
+            if app.wifi_tick() {
+                app.screen = Screen::Mirrors;
+                app.time_sync();
+                app.set_mirror_list();
+                app.mirror_country = None;
+                app.select_num = 0;
+            }
+            continue;
+        }
+
+        if let Some(key) = event::read().unwrap_or_else(|_| app.language.fail("error_input", &[])).as_key_press_event() {
             match app.screen {
                 Screen::LanguageSelection => match key.code {
                     KeyCode::Char('q') | KeyCode::Esc => break,
                     KeyCode::Char('d') => app.debug_mode = !app.debug_mode,
                     KeyCode::Up => {
-                        app.select_num = (app.select_num - 1).max(0);
-                        app.language = app.language_list.get(app.select_num).unwrap().clone();
-                        app.logs.push("Up".to_string());
+                        app.select_num = app.select_num.saturating_sub(1);
+                        app.set_language(app.select_num);
+                        app.logs.push(Log::new("log_up", &[]));
                     }
                     KeyCode::Down => {
                         app.select_num = (app.select_num + 1).min(app.language_list.len() - 1);
-                        app.language = app.language_list.get(app.select_num).unwrap().clone();
-                        app.logs.push("Down".to_string());
+                        app.set_language(app.select_num);
+                        app.logs.push(Log::new("log_down", &[]));
                     }
                     KeyCode::Enter => {
-                        if app.wifi_check() {
-                            app.screen = Screen::DiskSelection;
-                            app.editing = None;
-                            app.set_disk_list();
-                            app.disk = 0;
-                        } else {
-                            app.screen = Screen::WifiSelection;
-                            app.set_wifi_list();
-                        }
+                        app.logs.push(Log::new("log_language_select", &[&app.target_language.name]));
+                        app.locale_values[0] = app.target_language.get("locale");
+
+                        app.screen = Screen::Keyboard;
+                        app.set_keyboard_lists();
+                        app.set_keyboard_default();
+                        app.load_keymap();
+                        app.keyboard_picker = None;
                         app.select_num = 0;
                     }
                     _ => {}
                 }
 
-                Screen::WifiSelection => match key.code {
-                    KeyCode::Esc => match app.editing {
-                        None => {
+                Screen::Keyboard => match app.keyboard_picker {
+                    None => match key.code {
+                        KeyCode::Esc => {
                             app.screen = Screen::LanguageSelection;
-                            app.language = app.language_list.get(0).unwrap().clone();
+                            app.set_language(0);
                             app.select_num = 0;
                         }
-                        Some(_) => app.editing = None
-                    }
-                    KeyCode::Up => {
-                        if let None = app.editing {
-                            app.select_num = (app.select_num - 1).max(0);
+                        KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                        KeyCode::Down => app.select_num = (app.select_num + 1).min(3),
+                        KeyCode::Left | KeyCode::Right if app.select_num == 2 => {
+                            let index = TOGGLES.iter().position(|toggle| *toggle == app.keyboard.toggle).unwrap_or(0);
+                            let index = if key.code == KeyCode::Left {
+                                (index + TOGGLES.len() - 1) % TOGGLES.len()
+                            } else {
+                                (index + 1) % TOGGLES.len()
+                            };
+                            app.keyboard.toggle = TOGGLES[index].to_string();
+                        }
+                        KeyCode::Delete if app.select_num == 1 && app.keyboard.layouts.len() > 1 => {
+                            app.keyboard.layouts.pop();
+                        }
+                        KeyCode::Enter => match app.select_num {
+                            0 => {
+                                app.keyboard_picker = Some(KeyboardPicker::Keymap);
+                                app.select_num = 0;
+                            }
+                            1 => {
+                                app.keyboard_picker = Some(KeyboardPicker::Layout);
+                                app.select_num = 0;
+                            }
+                            2 => {}
+                            _ => {
+                                if app.wifi_check() {
+                                    app.screen = Screen::Mirrors;
+                                    app.editing = None;
+                                    app.time_sync();
+                                    app.set_mirror_list();
+                                    app.mirror_country = None;
+                                } else {
+                                    app.screen = Screen::Network;
+                                    app.select_num = 0;
+                                    app.set_interfaces();
+                                    if app.wifi_only() {
+                                        app.interface_select();
+                                    }
+                                }
+                                app.select_num = 0;
+                            }
                         }
+                        _ => {}
                     }
-                    KeyCode::Down => {
-                        if let None = app.editing {
-                            app.select_num = (app.select_num + 1).min(app.wifi_list.len() - 1);
+                    Some(_) => match key.code {
+                        KeyCode::Esc => {
+                            app.keyboard_picker = None;
+                            app.keyboard_search.clear();
+                            app.select_num = 0;
                         }
+                        KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                        KeyCode::Down => app.select_num = (app.select_num + 1)
+                            .min(app.keyboard_items().len().saturating_sub(1)),
+                        KeyCode::Enter => app.keyboard_select(),
+                        KeyCode::Char(value) => {
+                            app.keyboard_search.push(value);
+                            app.select_num = 0;
+                        }
+                        KeyCode::Backspace => {
+                            app.keyboard_search.pop();
+                            app.select_num = 0;
+                        }
+                        _ => {}
                     }
-                    KeyCode::Enter => match app.editing {
-                        None => {
-                            app.wifi.name = app.wifi_list.get(app.select_num)
-                                .expect("failed to wifi list get network name")
-                                .clone();
-                            app.wifi.password.clear();
-                            app.editing = Some(Editing::Password);
+                }
+
+                Screen::Network if app.wifi_connecting() => if let KeyCode::Esc = key.code {
+                    app.wifi_cancel();
+                }
 
-                            app.logs.push("Network select: ".to_string() + &app.wifi.name);
+                Screen::Network if app.manual_field.is_some() => match key.code {
+                    KeyCode::Esc => {
+                        app.manual_field = None;
+                        app.wifi_status = WifiStatus::Idle;
+                    }
+                    KeyCode::Up | KeyCode::BackTab => app.manual_field = app.manual_field.as_ref().map(ManualField::prev),
+                    KeyCode::Down | KeyCode::Tab => app.manual_field = app.manual_field.as_ref().map(ManualField::next),
+                    KeyCode::Left | KeyCode::Right if app.manual_field == Some(ManualField::Persist) => {
+                        if let Some(manual) = app.manual.as_mut() {
+                            manual.persist = manual.persist.next();
                         }
-                        Some(_) => {
+                    }
+                    KeyCode::Enter if app.manual.as_ref().is_some_and(|manual| validate_manual(manual).is_ok()) => {
+                        app.manual_apply();
+                    }
+                    KeyCode::Char(value) => {
+                        if let Some(input) = app.manual_input() {
+                            input.push(value);
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        if let Some(input) = app.manual_input() {
+                            input.pop();
+                        }
+                    }
+                    _ => {}
+                }
+
+                Screen::Network => match key.code {
+                    KeyCode::Esc => {
+                        app.screen = Screen::Keyboard;
+                        app.wifi_status = WifiStatus::Idle;
+                        app.select_num = 0;
+                    }
+                    KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                    KeyCode::Down => app.select_num = (app.select_num + 1).min(app.interfaces.len().saturating_sub(1)),
+                    KeyCode::Char('r') => {
+                        app.set_interfaces();
+                        app.select_num = app.select_num.min(app.interfaces.len().saturating_sub(1));
+                    }
+                    KeyCode::Char('m') => app.manual_open(),
+                    KeyCode::Enter => {
+                        app.wifi_status = WifiStatus::Idle;
+                        app.interface_select();
+                    }
+                    _ => {}
+                }
+
+                Screen::WifiSelection if app.wifi_connecting() => if let KeyCode::Esc = key.code {
+                    app.wifi_cancel();
+                }
+
+                Screen::WifiSelection if app.wifi_field.is_none() => match key.code {
+                    KeyCode::Esc => {
+                        app.screen = Screen::Network;
+                        app.wifi_status = WifiStatus::Idle;
+                        app.select_num = 0;
+                        app.set_interfaces();
+                    }
+                    KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                    KeyCode::Down => app.select_num = (app.select_num + 1).min(app.wifi_list.len()),
+                    KeyCode::Char('r') => app.wifi_rescan(),
+                    KeyCode::Enter => {
+                        app.wifi_select();
+                        if app.wifi_field.is_none() {
                             app.wifi_connect();
-                            if app.wifi_check() {
-                                app.screen = Screen::DiskSelection;
-                                app.editing = None;
-                                app.time_sync();
-                                app.set_disk_list();
-                                app.disk = 0;
-                            }
                         }
                     }
-                    KeyCode::Char(value) => if let Some(_) = app.editing {
-                        app.wifi.password.push(value);
+                    _ => {}
+                }
+
+                Screen::WifiSelection => match key.code {
+                    KeyCode::Esc => {
+                        app.wifi_field = None;
+                        app.wifi_status = WifiStatus::Idle;
+                    }
+                    KeyCode::Up | KeyCode::BackTab => app.wifi_move(false),
+                    KeyCode::Down | KeyCode::Tab => app.wifi_move(true),
+                    KeyCode::Left | KeyCode::Right if app.wifi_field == Some(WifiField::Eap) => {
+                        app.wifi_enterprise.eap = app.wifi_enterprise.eap.next();
                     }
-                    KeyCode::Backspace => if let Some(_) = app.editing {
-                        app.wifi.password.pop();
+                    KeyCode::Enter => app.wifi_connect(),
+                    KeyCode::Char(value) => {
+                        let field = app.wifi_field.clone().unwrap_or_else(|| app.language.fail("error_state_wifi_field", &[]));
+                        if let Some(input) = app.wifi_input(&field) {
+                            input.push(value);
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        let field = app.wifi_field.clone().unwrap_or_else(|| app.language.fail("error_state_wifi_field", &[]));
+                        if let Some(input) = app.wifi_input(&field) {
+                            input.pop();
+                        }
                     }
                     _ => {}
                 }
 
+                Screen::Mirrors => match app.mirror_country {
+                    None => match key.code {
+                        KeyCode::Esc => {
+                            app.screen = Screen::Network;
+                            app.mirror_search.clear();
+                            app.select_num = 0;
+                            app.set_interfaces();
+                        }
+                        KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                        KeyCode::Down => app.select_num = (app.select_num + 1)
+                            .min(app.mirror_items().len().saturating_sub(1)),
+                        // The mirrorlist of the ISO stays as it is
+                        KeyCode::Tab => {
+                            app.screen = Screen::DiskSelection;
+                            app.set_disk_list();
+                            app.disk = 0;
+                        }
+                        KeyCode::Enter => if let Some(country) = app.mirror_items().get(app.select_num).cloned() {
+                            app.mirror_country = Some(country);
+                            app.mirror_ranked = None;
+                            terminal.draw(|f| ui(f, &app)).unwrap_or_else(|_| app.language.fail("error_draw", &[]));
+                            app.mirror_rank();
+                            app.select_num = 0;
+                        }
+                        KeyCode::Char(value) => {
+                            app.mirror_search.push(value);
+                            app.select_num = 0;
+                        }
+                        KeyCode::Backspace => {
+                            app.mirror_search.pop();
+                            app.select_num = 0;
+                        }
+                        _ => {}
+                    }
+                    Some(_) => match key.code {
+                        KeyCode::Esc => {
+                            app.mirror_country = None;
+                            app.select_num = 0;
+                        }
+                        KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                        KeyCode::Down => app.select_num = (app.select_num + 1)
+                            .min(app.mirror_ranked.iter().flatten().count().saturating_sub(1)),
+                        KeyCode::Enter if app.mirror_ranked.iter().flatten().next().is_some() => {
+                            app.mirror_save();
+                            app.screen = Screen::DiskSelection;
+                            app.set_disk_list();
+                            app.disk = 0;
+                        }
+                        _ => {}
+                    }
+                }
+
                 Screen::DiskSelection => match key.code {
                     KeyCode::Esc => {
-                        app.screen = Screen::WifiSelection;
+                        app.screen = Screen::Mirrors;
+                        app.mirror_country = None;
                         app.select_num = 0;
                     }
                     KeyCode::Up => app.disk = (app.disk - 1).max(0),
@@ -132,22 +342,22 @@ fn run(mut terminal: DefaultTerminal) {
                     KeyCode::Up => app.select_num = (app.select_num - 1).max(0),
                     KeyCode::Down => app.select_num = (app.select_num + 1).min(app.disk_list
                         .get(app.disk)
-                        .expect("Error to part list")
+                        .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                         .1.len() - 1),
                     KeyCode::Enter => {
                         app.disk_tom = app.disk_list
                             .get(app.disk)
-                            .expect("Error to part list")
+                            .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                             .1.get(app.select_num)
-                            .expect("Error to part")
+                            .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                             .clone();
-                        app.logs.push("Select disk tom: ".to_string() + &app.disk_tom);
+                        app.logs.push(Log::new("log_part_select", &[&app.disk_tom]));
 
                         if app.efi_check() {
                             app.screen = Screen::Efipart;
                             app.select_num = 0;
                         } else {
-                            app.screen = Screen::UserSetup;
+                            app.screen = Screen::HostSetup;
                         }
                     }
                     _ => {}
@@ -160,55 +370,197 @@ fn run(mut terminal: DefaultTerminal) {
                     KeyCode::Up => app.select_num = (app.select_num - 1).max(0),
                     KeyCode::Down => app.select_num = (app.select_num + 1).min(app.disk_list
                         .get(app.disk)
-                        .expect("Error to part list for efi")
+                        .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                         .1.len() - 1),
                     KeyCode::Enter => {
                         app.disk_tom_efi = Some(app.disk_list
                             .get(app.disk)
-                            .expect("Error to part list for efi")
+                            .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                             .1.get(app.select_num)
-                            .expect("Error to part for efi")
+                            .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                             .clone());
-                        app.logs.push("Select disk tom for efi: ".to_string() + &app.disk_tom_efi.clone().expect("Error disk efi name"));
+                        app.logs.push(Log::new("log_efi_part_select", &[&app.disk_tom_efi.clone().unwrap_or_else(|| app.language.fail("error_state_partition", &[]))]));
 
-                        app.screen = Screen::UserSetup;
-                        app.user.name.clear();
-                        app.user.password.clear();
-                        app.editing = Some(Editing::Name);
+                        app.screen = Screen::HostSetup;
                     }
                     _ => {}
                 }
 
-                Screen::UserSetup => match key.code {
+                Screen::HostSetup => match key.code {
                     KeyCode::Esc => {
                         app.screen = Screen::Partitioning;
                         app.select_num = 0;
                     }
-                    KeyCode::Up => app.editing = Some(Editing::Name),
-                    KeyCode::Down => app.editing = Some(Editing::Password),
-                    KeyCode::Enter => match app.editing.clone().expect("Editing is None") {
-                        Editing::Name => app.editing = Some(Editing::Password),
-                        Editing::Password => {
-                            app.logs.push("User create: ".to_string() + &app.user.name);
-                            app.select_num = 0;
-                            app.screen = Screen::Installing;
-                            app.mount_and_format();
-                            app.set_install_list();
-                            for _ in app.install_list.clone() {
-                                terminal.draw(|f| ui(f, &app)).expect("Error draw for install");
-                                app.install();
-                                app.select_num += 1;
-                            }
-                            app.complite();
+                    KeyCode::Enter if validate_hostname(&app.hostname).is_ok() => {
+                        app.logs.push(Log::new("log_hostname_select", &[&app.hostname]));
+
+                        app.screen = Screen::Timezone;
+                        app.set_timezone_list();
+                        app.timezone_region = None;
+                        app.timezone_search.clear();
+                        app.select_num = 0;
+                    }
+                    KeyCode::Char(value) => app.hostname.push(value),
+                    KeyCode::Backspace => { app.hostname.pop(); }
+                    _ => {}
+                }
+
+                Screen::Timezone => match key.code {
+                    KeyCode::Esc => if app.timezone_region.is_some() {
+                        app.timezone_region = None;
+                        app.timezone_search.clear();
+                        app.select_num = 0;
+                    } else {
+                        app.screen = Screen::HostSetup;
+                    }
+                    KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                    KeyCode::Down => app.select_num = (app.select_num + 1)
+                        .min(app.timezone_items().len().saturating_sub(1)),
+                    KeyCode::Tab => app.rtc_local = !app.rtc_local,
+                    KeyCode::Enter => {
+                        let selected = app.timezone_select();
+                        if selected {
+                            app.screen = Screen::Locale;
+                            app.set_locale_list();
+                            app.locale_category = None;
+                            app.select_num = 0;
+                        }
+                    }
+                    KeyCode::Char(value) => {
+                        app.timezone_search.push(value);
+                        app.select_num = 0;
+                    }
+                    KeyCode::Backspace => {
+                        app.timezone_search.pop();
+                        app.select_num = 0;
+                    }
+                    _ => {}
+                }
+
+                Screen::Locale => match app.locale_category {
+                    None => match key.code {
+                        KeyCode::Esc => {
+                            app.screen = Screen::Timezone;
+                            app.timezone_region = None;
+                            app.timezone_search.clear();
+                            app.select_num = 0;
+                        }
+                        KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                        KeyCode::Down => app.select_num = (app.select_num + 1).min(CATEGORIES.len()),
+                        KeyCode::Delete if app.select_num > 0 && app.select_num < CATEGORIES.len() => {
+                            app.locale_values[app.select_num].clear();
+                        }
+                        KeyCode::Enter => if app.select_num < CATEGORIES.len() {
+                            app.locale_category = Some(app.select_num);
+                            app.locale_search.clear();
+                            app.select_num = 0;
+                        } else if !app.locale_values[0].is_empty() {
+                            app.screen = Screen::UserList;
+                            app.select_num = 0;
+                        }
+                        _ => {}
+                    }
+                    Some(category) => match key.code {
+                        KeyCode::Esc => {
+                            app.locale_category = None;
+                            app.locale_search.clear();
+                            app.select_num = category;
+                        }
+                        KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                        KeyCode::Down => app.select_num = (app.select_num + 1)
+                            .min(app.locale_items().len().saturating_sub(1)),
+                        KeyCode::Enter => app.locale_select(),
+                        KeyCode::Char(value) => {
+                            app.locale_search.push(value);
+                            app.select_num = 0;
+                        }
+                        KeyCode::Backspace => {
+                            app.locale_search.pop();
+                            app.select_num = 0;
                         }
+                        _ => {}
+                    }
+                }
+
+                Screen::UserList => match key.code {
+                    KeyCode::Esc => {
+                        app.screen = Screen::Locale;
+                        app.locale_category = None;
+                        app.select_num = 0;
+                    }
+                    KeyCode::Up => app.select_num = app.select_num.saturating_sub(1),
+                    KeyCode::Down => app.select_num = (app.select_num + 1).min(app.users.len() + 1),
+                    KeyCode::Delete if app.select_num < app.users.len() => {
+                        let user = app.users.remove(app.select_num);
+                        app.logs.push(Log::new("log_user_remove", &[&user.name]));
                     }
-                    KeyCode::Char(value) => match app.editing.clone().expect("Editing is None") {
+                    KeyCode::Enter => if app.select_num < app.users.len() {
+                        app.edit_user(Some(app.select_num));
+                    } else if app.select_num == app.users.len() {
+                        app.edit_user(None);
+                    } else if app.users.iter().any(|user| user.admin) && app.install_ready() {
+                        app.select_num = 0;
+                        app.screen = Screen::Installing;
+                        terminal.draw(|f| ui(f, &app)).unwrap_or_else(|_| app.language.fail("error_draw", &[]));
+                        app.keyring_preflight();
+                        app.mount_and_format();
+                        app.set_install_list();
+                        for _ in app.install_list.clone() {
+                            terminal.draw(|f| ui(f, &app)).unwrap_or_else(|_| app.language.fail("error_draw", &[]));
+                            app.install();
+                            app.select_num += 1;
+                        }
+                        app.complite();
+                    }
+                    _ => {}
+                }
+
+                Screen::UserSetup => match key.code {
+                    KeyCode::Esc => {
+                        app.screen = Screen::UserList;
+                        app.editing = None;
+                    }
+                    KeyCode::Up => app.editing = Some(app.editing.clone().unwrap_or_else(|| app.language.fail("error_state_editing", &[])).prev()),
+                    KeyCode::Down => app.editing = Some(app.editing.clone().unwrap_or_else(|| app.language.fail("error_state_editing", &[])).next()),
+                    KeyCode::Left => match app.editing {
+                        Some(Editing::Groups) => app.group_select = app.group_select.saturating_sub(1),
+                        Some(Editing::Shell) => app.user.shell = app.user.shell.prev(),
+                        _ => {}
+                    }
+                    KeyCode::Right => match app.editing {
+                        Some(Editing::Groups) => app.group_select = (app.group_select + 1).min(GROUPS.len() - 1),
+                        Some(Editing::Shell) => app.user.shell = app.user.shell.next(),
+                        _ => {}
+                    }
+                    KeyCode::Enter => match app.editing.clone().unwrap_or_else(|| app.language.fail("error_state_editing", &[])) {
+                        Editing::Name => if app.user_name_check().is_ok() {
+                            app.editing = Some(Editing::Password);
+                        }
+                        // An empty password would also become the one of root
+                        Editing::Shell => if app.user_name_check().is_ok()
+                            && !app.user.password.is_empty()
+                            && app.user.password == app.password_repeat {
+                            app.save_user();
+                        }
+                        editing => app.editing = Some(editing.next()),
+                    }
+                    KeyCode::Char(value) => match app.editing.clone().unwrap_or_else(|| app.language.fail("error_state_editing", &[])) {
                         Editing::Name => app.user.name.push(value),
                         Editing::Password => app.user.password.push(value),
+                        Editing::PasswordRepeat => app.password_repeat.push(value),
+                        Editing::Admin => if value == ' ' {
+                            app.user.admin = !app.user.admin;
+                        }
+                        Editing::Groups => if value == ' ' {
+                            app.user.toggle_group(GROUPS[app.group_select]);
+                        }
+                        Editing::Shell => {}
                     }
-                    KeyCode::Backspace => match app.editing.clone().expect("Editing is None") {
+                    KeyCode::Backspace => match app.editing.clone().unwrap_or_else(|| app.language.fail("error_state_editing", &[])) {
                         Editing::Name => { app.user.name.pop(); }
                         Editing::Password => { app.user.password.pop(); }
+                        Editing::PasswordRepeat => { app.password_repeat.pop(); }
+                        _ => {}
                     }
                     _ => {}
                 }
@@ -0,0 +1,163 @@
+use std::{process::Command, thread, time::Duration};
+
+use crate::network::set_proxy;
+
+
+pub const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+// Every mirror under its country, reflector on the ISO rewrites the local list without them
+pub const MIRRORLIST_ALL: &str = "https://archlinux.org/mirrorlist/all/";
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+pub const RANK_TIMEOUT: Duration = Duration::from_secs(3);
+// More would only slow the ranking down, the fastest few are all pacman uses anyway
+pub const RANK_LIMIT: usize = 20;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Mirror {
+    pub country: String,
+    pub url: String,
+}
+
+// Servers under their ## Country header, commented ones too since the stock list has them all commented.
+// Banners of # like the one from reflector are not countries.
+pub fn parse_mirrorlist(text: &str) -> Vec<Mirror> {
+    let mut mirrors: Vec<Mirror> = Vec::new();
+    let mut country = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix("##") {
+            if !header.starts_with('#') {
+                country = header.trim().to_string();
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.trim_start_matches('#').split_once('=') else {
+            continue;
+        };
+        let url = value.trim();
+        if key.trim() == "Server" && !url.is_empty() && !mirrors.iter().any(|mirror| mirror.url == url) {
+            mirrors.push(Mirror { country: country.clone(), url: url.to_string() });
+        }
+    }
+    mirrors
+}
+
+pub fn countries(mirrors: &[Mirror]) -> Vec<String> {
+    let mut countries: Vec<String> = mirrors.iter()
+        .filter(|mirror| !mirror.country.is_empty())
+        .map(|mirror| mirror.country.clone())
+        .collect();
+    countries.sort();
+    countries.dedup();
+    countries
+}
+
+// Every mirror keeps a tiny lastupdate file next to the repositories
+fn probe_url(url: &str) -> String {
+    match url.find("$repo") {
+        Some(index) => url[..index].to_string() + "lastupdate",
+        None => url.trim_end_matches('/').to_string() + "/lastupdate",
+    }
+}
+
+pub fn time_download(url: &str, timeout: Duration, proxy: &str) -> Option<Duration> {
+    let output = set_proxy(&mut Command::new("curl"), proxy)
+        .args(["--silent", "--fail", "--output", "/dev/null"])
+        .args(["--max-time", &timeout.as_secs().max(1).to_string()])
+        .args(["--write-out", "%{time_total}"])
+        .arg(probe_url(url))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let seconds: f64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(Duration::from_secs_f64(seconds))
+}
+
+pub fn fetch_mirrorlist(timeout: Duration, proxy: &str) -> Option<String> {
+    let output = set_proxy(&mut Command::new("curl"), proxy)
+        .args(["--silent", "--fail", "--location"])
+        .args(["--max-time", &timeout.as_secs().max(1).to_string()])
+        .arg(MIRRORLIST_ALL)
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Fastest first, mirrors that fail or time out are left out
+pub fn rank(mirrors: &[Mirror], timeout: Duration, proxy: &str) -> Vec<(Mirror, Duration)> {
+    let threads: Vec<_> = mirrors.iter().take(RANK_LIMIT).cloned()
+        .map(|mirror| {
+            let proxy = proxy.to_string();
+            thread::spawn(move || time_download(&mirror.url, timeout, &proxy).map(|time| (mirror, time)))
+        })
+        .collect();
+
+    let mut ranked: Vec<(Mirror, Duration)> = threads.into_iter()
+        .filter_map(|thread| thread.join().ok().flatten())
+        .collect();
+    ranked.sort_by_key(|(_, time)| *time);
+    ranked
+}
+
+pub fn mirrorlist(mirrors: &[Mirror]) -> String {
+    let mut text = "## Ranked by download time\n".to_string();
+    for mirror in mirrors {
+        text += &format!("Server = {}\n", mirror.url);
+    }
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in::{stand_in, NOT_FOUND, OK};
+
+    // Answers after the delay, with 404 when it has no lastupdate
+    fn server(delay: Duration, found: bool) -> String {
+        let port = stand_in(Some(if found { OK } else { NOT_FOUND }), delay);
+        format!("http://127.0.0.1:{}/archlinux/$repo/os/$arch", port)
+    }
+
+    fn mirror(country: &str, url: &str) -> Mirror {
+        Mirror { country: country.to_string(), url: url.to_string() }
+    }
+
+    #[test]
+    fn parse() {
+        let mirrors = parse_mirrorlist(include_str!("../fixtures/mirror/mirrorlist.txt"));
+        assert_eq!(mirrors, vec![
+            mirror("Worldwide", "https://geo.mirror.pkgbuild.com/$repo/os/$arch"),
+            mirror("Worldwide", "https://mirror.rackspace.com/archlinux/$repo/os/$arch"),
+            mirror("Germany", "https://ftp.fau.de/archlinux/$repo/os/$arch"),
+            mirror("Germany", "https://mirror.netcologne.de/archlinux/$repo/os/$arch"),
+            mirror("Japan", "https://ftp.jaist.ac.jp/pub/Linux/ArchLinux/$repo/os/$arch"),
+        ]);
+        assert_eq!(countries(&mirrors), vec!["Germany", "Japan", "Worldwide"]);
+        assert_eq!(probe_url(&mirrors[0].url), "https://geo.mirror.pkgbuild.com/lastupdate");
+
+        let mirrors = parse_mirrorlist(include_str!("../fixtures/mirror/mirrorlist_reflector.txt"));
+        assert_eq!(mirrors, vec![
+            mirror("", "https://ftp.fau.de/archlinux/$repo/os/$arch"),
+            mirror("", "https://mirror.netcologne.de/archlinux/$repo/os/$arch"),
+            mirror("", "https://ftp.jaist.ac.jp/pub/Linux/ArchLinux/$repo/os/$arch"),
+        ]);
+        assert!(countries(&mirrors).is_empty());
+    }
+
+    #[test]
+    fn rank_by_time() {
+        let slow = mirror("Germany", &server(Duration::from_millis(400), true));
+        let fast = mirror("Germany", &server(Duration::ZERO, true));
+        let missing = mirror("Germany", &server(Duration::ZERO, false));
+        let hanging = mirror("Germany", &server(Duration::from_secs(5), true));
+
+        let ranked = rank(&[slow.clone(), missing, hanging, fast.clone()], Duration::from_secs(1), "");
+        let order: Vec<Mirror> = ranked.into_iter().map(|(mirror, _)| mirror).collect();
+        assert_eq!(order, vec![fast.clone(), slow.clone()]);
+        assert_eq!(mirrorlist(&order), format!("## Ranked by download time\nServer = {}\nServer = {}\n", fast.url, slow.url));
+    }
+}
@@ -0,0 +1,1107 @@
+use std::{cmp::Reverse, fs, net::{IpAddr, Ipv4Addr}, os::unix::fs::PermissionsExt, path::Path, process::{Command, Stdio}, sync::Arc};
+
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Security {
+    Open,
+    Psk,
+    Enterprise,
+}
+impl Security {
+    pub fn name(&self) -> &str {
+        match self {
+            Security::Open => "open",
+            Security::Psk => "psk",
+            Security::Enterprise => "8021x",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Network {
+    pub name: String,
+    pub security: Security,
+    // 0-4 like the bars of iwctl
+    pub signal: u8,
+    pub connected: bool,
+}
+
+// Hidden networks are not in the scan, a typed password is all there is to go by
+pub fn hidden_security(password: &str) -> Security {
+    if password.is_empty() {
+        Security::Open
+    } else {
+        Security::Psk
+    }
+}
+
+// Stable, so networks with the same signal keep the order the backend gave them
+pub fn sort_networks(networks: &mut [Network]) {
+    networks.sort_by_key(|network| Reverse(network.signal));
+}
+
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Eap {
+    Peap,
+    Ttls,
+}
+impl Eap {
+    pub fn all() -> Vec<Eap> {
+        vec![Eap::Peap, Eap::Ttls]
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Eap::Peap => "PEAP",
+            Eap::Ttls => "TTLS",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let methods = Eap::all();
+        let index = methods.iter().position(|eap| eap == self).expect("Unknown EAP method");
+        methods[(index + 1) % methods.len()].clone()
+    }
+}
+
+// WPA2-Enterprise with a password inside the tunnel, MSCHAPv2 is what eduroam and most offices use
+#[derive(Clone, PartialEq, Debug)]
+pub struct Enterprise {
+    pub eap: Eap,
+    pub identity: String,
+    pub password: String,
+    pub ca_cert: String,
+}
+impl Enterprise {
+    pub fn new() -> Self {
+        Self {
+            eap: Eap::Peap,
+            identity: String::new(),
+            password: String::new(),
+            ca_cert: String::new(),
+        }
+    }
+}
+
+
+// Run and Save carry the program that could not be started or the file that could not be written,
+// Auth and Failed come from a backend that refused to connect and can be retried.
+#[derive(Debug, PartialEq)]
+pub enum NetworkError {
+    Run(String),
+    Save(String),
+    Auth,
+    Failed(String),
+}
+
+// Send and Sync so connecting can run in a thread while the screen shows progress
+pub trait NetworkBackend: Send + Sync {
+    fn name(&self) -> &str;
+    fn devices(&self) -> Result<Vec<String>, NetworkError>;
+    fn scan(&self, device: &str) -> Result<(), NetworkError>;
+    fn networks(&self, device: &str) -> Result<Vec<Network>, NetworkError>;
+    // An empty password connects to an open network
+    fn connect(&self, device: &str, network: &str, password: &str) -> Result<(), NetworkError>;
+    fn connect_hidden(&self, device: &str, network: &str, password: &str) -> Result<(), NetworkError>;
+    fn connect_enterprise(&self, device: &str, network: &str, enterprise: &Enterprise) -> Result<(), NetworkError>;
+}
+
+
+fn run(program: &str, args: &[&str]) -> Result<String, NetworkError> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|_| NetworkError::Run(program.to_string()))?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Like run, but a failed exit status is turned into an error by the backend
+fn run_connect(program: &str, args: &[&str], error: impl Fn(&str) -> NetworkError) -> Result<(), NetworkError> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|_| NetworkError::Run(program.to_string()))?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let text = String::from_utf8_lossy(&output.stderr).to_string() + &String::from_utf8_lossy(&output.stdout);
+    Err(error(strip_ansi(&text).trim()))
+}
+
+// Output of ip -4 -o address show dev X: "2: eth0    inet 192.168.1.5/24 brd ..."
+pub fn parse_address(text: &str) -> Option<String> {
+    let mut words = text.split_whitespace();
+    words.find(|word| *word == "inet")?;
+    words.next().map(String::from)
+}
+
+pub fn address(device: &str) -> Option<String> {
+    let output = Command::new("ip")
+        .args(["-4", "-o", "address", "show", "dev", device])
+        .output()
+        .ok()?;
+    parse_address(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Associated but without an IPv4 address means DHCP did not answer
+pub fn has_address(device: &str) -> bool {
+    address(device).is_some()
+}
+
+
+pub const SYS_NET: &str = "/sys/class/net";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Link {
+    Ethernet,
+    Wifi,
+    Usb,
+}
+impl Link {
+    pub fn name(&self) -> &str {
+        match self {
+            Link::Ethernet => "network_ethernet",
+            Link::Wifi => "network_wifi",
+            Link::Usb => "network_usb",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Interface {
+    pub name: String,
+    pub link: Link,
+    pub up: bool,
+    pub address: Option<String>,
+}
+
+// Only interfaces backed by hardware have a device link, that skips lo, bridges, veth and tunnels.
+// Phones and USB adapters show up as ethernet on the usb bus.
+pub fn interface_list(sys: &Path) -> Vec<Interface> {
+    let mut interfaces = Vec::new();
+    let Ok(entries) = fs::read_dir(sys) else {
+        return interfaces;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !path.join("device").exists() {
+            continue;
+        }
+
+        let subsystem = fs::read_link(path.join("device/subsystem")).unwrap_or_default();
+        let link = if path.join("wireless").exists() || path.join("phy80211").exists() {
+            Link::Wifi
+        } else if subsystem.file_name().is_some_and(|name| name == "usb") {
+            Link::Usb
+        } else {
+            Link::Ethernet
+        };
+        let up = fs::read_to_string(path.join("operstate")).map(|state| state.trim() == "up").unwrap_or(false);
+
+        interfaces.push(Interface { name, link, up, address: None });
+    }
+
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces
+}
+
+// Whatever manages wired links on the live system gets asked, dhcpcd when nothing does
+pub fn dhcp(device: &str) -> Result<(), NetworkError> {
+    if service_active("NetworkManager") {
+        return run_connect("nmcli", &["device", "connect", device], nmcli_error);
+    }
+
+    run("ip", &["link", "set", device, "up"])?;
+    if service_active("systemd-networkd") {
+        return run_connect("networkctl", &["reconfigure", device], |text| NetworkError::Failed(text.to_string()));
+    }
+    run_connect("dhcpcd", &["--waitip=4", "--timeout", "20", device], |text| NetworkError::Failed(text.to_string()))
+}
+
+fn service_active(service: &str) -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", service])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+// iwd is what the Arch ISO runs, NetworkManager is what most rescue images run
+pub fn detect_backend() -> Option<Arc<dyn NetworkBackend>> {
+    if service_active("iwd") {
+        return Some(Arc::new(Iwd));
+    }
+    if service_active("NetworkManager") {
+        return Some(Arc::new(NetworkManager));
+    }
+    None
+}
+
+
+pub struct Iwd;
+
+pub const IWD_DIR: &str = "/var/lib/iwd";
+pub const NM_CERTS: &str = "/etc/NetworkManager/certs";
+
+// iwctl colours its tables even when the output is not a terminal
+pub fn strip_ansi(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            result.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'[') {
+            chars.next();
+            // Parameters and intermediates until the final byte in @..~
+            for next in chars.by_ref() {
+                if ('@'..='~').contains(&next) {
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+// Tables of iwctl: a title, a rule, the column names, another rule and then the rows
+fn iwctl_rows(text: &str) -> Vec<String> {
+    let text = strip_ansi(text);
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.iter().rposition(|line| line.trim_start().starts_with("---")).map(|index| index + 1).unwrap_or(0);
+
+    lines[start..].iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+pub fn parse_iwctl_devices(text: &str) -> Vec<String> {
+    iwctl_rows(text).iter()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+// Signal is **** without colours or dBm with rssi-dbms, where iwd draws a bar for -60, -67 and -75
+fn iwctl_signal(text: &str) -> u8 {
+    match text.parse::<i32>() {
+        Ok(dbm) if dbm >= -60 => 4,
+        Ok(dbm) if dbm >= -67 => 3,
+        Ok(dbm) if dbm >= -75 => 2,
+        Ok(_) => 1,
+        Err(_) => text.chars().filter(|c| *c == '*').count().min(4) as u8,
+    }
+}
+
+// Security and signal never contain spaces, so they are taken from the right
+// and everything before them is the name, whatever spaces it has.
+pub fn parse_iwctl_networks(text: &str) -> Vec<Network> {
+    let mut networks = Vec::new();
+    for line in iwctl_rows(text) {
+        let line = line.trim();
+        let (connected, line) = match line.strip_prefix('>') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, line),
+        };
+
+        let Some((rest, signal)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        let Some((name, security)) = rest.trim_end().rsplit_once(' ') else {
+            continue;
+        };
+
+        let security = match security {
+            "open" => Security::Open,
+            "8021x" => Security::Enterprise,
+            _ => Security::Psk,
+        };
+        networks.push(Network {
+            name: name.trim_end().to_string(),
+            security,
+            signal: iwctl_signal(signal),
+            connected,
+        });
+    }
+    sort_networks(&mut networks);
+    networks
+}
+
+// iwd answers a rejected passphrase with a bare "Operation failed"
+pub fn iwctl_error(text: &str, secured: bool) -> NetworkError {
+    let lower = text.to_lowercase();
+    if lower.contains("invalid passphrase") || lower.contains("invalid format") || (secured && lower.contains("operation failed")) {
+        NetworkError::Auth
+    } else {
+        NetworkError::Failed(text.to_string())
+    }
+}
+
+// Names with anything but letters, digits, space, - and _ are stored hex encoded behind =
+pub fn iwd_profile_name(network: &str, security: &Security) -> String {
+    let extension = match security {
+        Security::Open => ".open",
+        Security::Psk => ".psk",
+        Security::Enterprise => ".8021x",
+    };
+    if network.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_') {
+        network.to_string() + extension
+    } else {
+        let hex: String = network.bytes().map(|byte| format!("{:02x}", byte)).collect();
+        "=".to_string() + &hex + extension
+    }
+}
+
+pub fn iwd_profile(enterprise: &Enterprise) -> String {
+    let eap = enterprise.eap.name();
+    let mut text = format!("[Security]
+EAP-Method={eap}
+EAP-Identity={}
+", enterprise.identity);
+    if !enterprise.ca_cert.is_empty() {
+        text += &format!("EAP-{eap}-CACert={}\n", enterprise.ca_cert);
+    }
+    text += &format!("EAP-{eap}-Phase2-Method=MSCHAPV2
+EAP-{eap}-Phase2-Identity={}
+EAP-{eap}-Phase2-Password={}
+", enterprise.identity, enterprise.password);
+    text
+}
+
+impl NetworkBackend for Iwd {
+    fn name(&self) -> &str {
+        "iwd"
+    }
+
+    fn devices(&self) -> Result<Vec<String>, NetworkError> {
+        run("iwctl", &["station", "list"]).map(|text| parse_iwctl_devices(&text))
+    }
+
+    fn scan(&self, device: &str) -> Result<(), NetworkError> {
+        run("iwctl", &["station", device, "scan"]).map(|_| ())
+    }
+
+    fn networks(&self, device: &str) -> Result<Vec<Network>, NetworkError> {
+        run("iwctl", &["station", device, "get-networks", "rssi-dbms"]).map(|text| parse_iwctl_networks(&text))
+    }
+
+    fn connect(&self, device: &str, network: &str, password: &str) -> Result<(), NetworkError> {
+        let mut args = Vec::new();
+        if !password.is_empty() {
+            args.extend(["--passphrase", password]);
+        }
+        args.extend(["station", device, "connect", network]);
+        run_connect("iwctl", &args, |text| iwctl_error(text, !password.is_empty()))
+    }
+
+    fn connect_hidden(&self, device: &str, network: &str, password: &str) -> Result<(), NetworkError> {
+        let mut args = Vec::new();
+        if !password.is_empty() {
+            args.extend(["--passphrase", password]);
+        }
+        args.extend(["station", device, "connect-hidden", network]);
+        run_connect("iwctl", &args, |text| iwctl_error(text, !password.is_empty()))
+    }
+
+    // iwd cannot ask for 802.1X settings interactively, it only reads them from a profile
+    fn connect_enterprise(&self, device: &str, network: &str, enterprise: &Enterprise) -> Result<(), NetworkError> {
+        let path = format!("{}/{}", IWD_DIR, iwd_profile_name(network, &Security::Enterprise));
+        fs::create_dir_all(IWD_DIR).map_err(|_| NetworkError::Save(IWD_DIR.to_string()))?;
+        fs::write(&path, iwd_profile(enterprise)).map_err(|_| NetworkError::Save(path.clone()))?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|_| NetworkError::Save(path.clone()))?;
+
+        run_connect("iwctl", &["station", device, "connect", network], |text| iwctl_error(text, true))
+    }
+}
+
+
+pub struct NetworkManager;
+
+// Terse output of nmcli separates fields with : and escapes : and \ inside values
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => if let Some(next) = chars.next() {
+                fields.last_mut().expect("No field").push(next);
+            }
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().expect("No field").push(c),
+        }
+    }
+    fields
+}
+
+pub fn nmcli_error(text: &str) -> NetworkError {
+    let lower = text.to_lowercase();
+    if lower.contains("secrets were required") || lower.contains("802.1x supplicant") || lower.contains("invalid key") {
+        NetworkError::Auth
+    } else {
+        NetworkError::Failed(text.to_string())
+    }
+}
+
+// Output of nmcli -t -f DEVICE,TYPE device
+pub fn parse_nmcli_devices(text: &str) -> Vec<String> {
+    text.lines()
+        .map(split_terse)
+        .filter(|fields| fields.len() == 2 && fields[1] == "wifi")
+        .map(|fields| fields[0].clone())
+        .collect()
+}
+
+// Output of nmcli -t -f IN-USE,SSID,SECURITY,SIGNAL device wifi list,
+// one row per access point so names repeat and the strongest one is kept
+pub fn parse_nmcli_networks(text: &str) -> Vec<Network> {
+    let mut networks: Vec<Network> = Vec::new();
+    for line in text.lines() {
+        let fields = split_terse(line);
+        let [in_use, name, security, signal] = fields.as_slice() else {
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+
+        let security = if security.contains("802.1X") {
+            Security::Enterprise
+        } else if security.is_empty() || security == "--" {
+            Security::Open
+        } else {
+            Security::Psk
+        };
+        // Percent, split the same way as the bars of nmcli
+        let signal = match signal.parse::<u8>().unwrap_or(0) {
+            0 => 0,
+            1..=24 => 1,
+            25..=49 => 2,
+            50..=74 => 3,
+            _ => 4,
+        };
+        let network = Network { name: name.clone(), security, signal, connected: in_use == "*" };
+
+        match networks.iter_mut().find(|item| item.name == network.name) {
+            Some(item) => {
+                item.connected |= network.connected;
+                if network.signal > item.signal {
+                    item.signal = network.signal;
+                }
+            }
+            None => networks.push(network),
+        }
+    }
+    sort_networks(&mut networks);
+    networks
+}
+
+impl NetworkBackend for NetworkManager {
+    fn name(&self) -> &str {
+        "NetworkManager"
+    }
+
+    fn devices(&self) -> Result<Vec<String>, NetworkError> {
+        run("nmcli", &["-t", "-f", "DEVICE,TYPE", "device"]).map(|text| parse_nmcli_devices(&text))
+    }
+
+    fn scan(&self, device: &str) -> Result<(), NetworkError> {
+        run("nmcli", &["device", "wifi", "rescan", "ifname", device]).map(|_| ())
+    }
+
+    fn networks(&self, device: &str) -> Result<Vec<Network>, NetworkError> {
+        run("nmcli", &["-t", "-f", "IN-USE,SSID,SECURITY,SIGNAL", "device", "wifi", "list", "ifname", device])
+            .map(|text| parse_nmcli_networks(&text))
+    }
+
+    fn connect(&self, device: &str, network: &str, password: &str) -> Result<(), NetworkError> {
+        let mut args = vec!["device", "wifi", "connect", network];
+        if !password.is_empty() {
+            args.extend(["password", password]);
+        }
+        args.extend(["ifname", device]);
+        run_connect("nmcli", &args, nmcli_error)
+    }
+
+    fn connect_hidden(&self, device: &str, network: &str, password: &str) -> Result<(), NetworkError> {
+        let mut args = vec!["device", "wifi", "connect", network];
+        if !password.is_empty() {
+            args.extend(["password", password]);
+        }
+        args.extend(["hidden", "yes", "ifname", device]);
+        run_connect("nmcli", &args, nmcli_error)
+    }
+
+    // nmcli saves the profile to /etc/NetworkManager/system-connections
+    fn connect_enterprise(&self, device: &str, network: &str, enterprise: &Enterprise) -> Result<(), NetworkError> {
+        nmcli_delete(network);
+        let args = nmcli_enterprise_args(device, network, enterprise);
+        run_connect("nmcli", &args.iter().map(String::as_str).collect::<Vec<_>>(), nmcli_error)?;
+
+        run_connect("nmcli", &["connection", "up", "id", network], nmcli_error)
+    }
+}
+
+// A profile left by an earlier attempt would share the id and `connection up` could pick it,
+// nmcli fails when there is none, which is fine
+fn nmcli_delete(id: &str) {
+    let _ = run("nmcli", &["connection", "delete", "id", id]);
+}
+
+pub fn nmcli_enterprise_args(device: &str, network: &str, enterprise: &Enterprise) -> Vec<String> {
+    let mut args = vec![
+        "connection", "add", "type", "wifi", "con-name", network, "ifname", device, "ssid", network,
+        "wifi-sec.key-mgmt", "wpa-eap",
+        "802-1x.eap", &enterprise.eap.name().to_lowercase(),
+        "802-1x.phase2-auth", "mschapv2",
+        "802-1x.identity", &enterprise.identity,
+        "802-1x.password", &enterprise.password,
+    ].into_iter().map(String::from).collect::<Vec<_>>();
+    if !enterprise.ca_cert.is_empty() {
+        args.extend(["802-1x.ca-cert".to_string(), enterprise.ca_cert.clone()]);
+    }
+    args
+}
+
+
+#[derive(Clone, PartialEq)]
+pub enum Persist {
+    No,
+    NetworkManager,
+    Networkd,
+}
+impl Persist {
+    pub fn all() -> Vec<Persist> {
+        vec![Persist::No, Persist::NetworkManager, Persist::Networkd]
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Persist::No => "manual_persist_no",
+            Persist::NetworkManager => "NetworkManager",
+            Persist::Networkd => "systemd-networkd",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let all = Persist::all();
+        let index = all.iter().position(|persist| persist == self).expect("Unknown persist");
+        all[(index + 1) % all.len()].clone()
+    }
+}
+
+// The Wi-Fi used during the install, kept so the installed system knows it on first boot
+#[derive(Clone, PartialEq, Debug)]
+pub struct WifiProfile {
+    pub ssid: String,
+    pub security: Security,
+    pub hidden: bool,
+    // Passphrase, or the 64 hex digit key iwd stores once it has hashed it
+    pub psk: String,
+    pub enterprise: Enterprise,
+}
+
+fn profile_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    text.lines().find_map(|line| {
+        let (name, value) = line.trim().split_once('=')?;
+        (name.trim() == key).then(|| value.trim())
+    })
+}
+
+// What iwd saved wins over what was typed, it is what actually connected
+pub fn profile_from_iwd(base: &WifiProfile, text: &str) -> WifiProfile {
+    let mut profile = base.clone();
+    if let Some(hidden) = profile_value(text, "Hidden") {
+        profile.hidden = hidden == "true";
+    }
+    if let Some(psk) = profile_value(text, "Passphrase").or(profile_value(text, "PreSharedKey")) {
+        profile.psk = psk.to_string();
+    }
+    if let Some(method) = profile_value(text, "EAP-Method") {
+        if let Some(eap) = Eap::all().into_iter().find(|eap| eap.name() == method) {
+            profile.enterprise.eap = eap;
+        }
+        let eap = profile.enterprise.eap.name().to_string();
+        if let Some(identity) = profile_value(text, &format!("EAP-{eap}-Phase2-Identity")).or(profile_value(text, "EAP-Identity")) {
+            profile.enterprise.identity = identity.to_string();
+        }
+        if let Some(password) = profile_value(text, &format!("EAP-{eap}-Phase2-Password")) {
+            profile.enterprise.password = password.to_string();
+        }
+        if let Some(ca_cert) = profile_value(text, &format!("EAP-{eap}-CACert")) {
+            profile.enterprise.ca_cert = ca_cert.to_string();
+        }
+    }
+    profile
+}
+
+// Slashes cannot be in a file name, NetworkManager goes by the id inside anyway
+pub fn nm_keyfile_name(ssid: &str) -> String {
+    ssid.replace('/', "_") + ".nmconnection"
+}
+
+// The certificate typed on the live system is not in the target, it is copied next to the profiles.
+// Returns the path to copy from, the profile then points at the copy.
+pub fn move_ca_cert(profile: &mut WifiProfile) -> Option<String> {
+    if profile.security != Security::Enterprise || profile.enterprise.ca_cert.is_empty() {
+        return None;
+    }
+    let target = format!("{}/{}.pem", NM_CERTS, profile.ssid.replace('/', "_"));
+    Some(std::mem::replace(&mut profile.enterprise.ca_cert, target))
+}
+
+pub fn nm_wifi_keyfile(profile: &WifiProfile) -> String {
+    let mut text = format!("[connection]
+id={ssid}
+type=wifi
+
+[wifi]
+mode=infrastructure
+ssid={ssid}
+", ssid = profile.ssid);
+    if profile.hidden {
+        text += "hidden=true\n";
+    }
+
+    match profile.security {
+        // NetworkManager rejects wpa-psk without a key, and it could not have connected either
+        Security::Open => {}
+        Security::Psk if profile.psk.is_empty() => {}
+        Security::Psk => text += &format!("
+[wifi-security]
+key-mgmt=wpa-psk
+psk={}
+", profile.psk),
+        Security::Enterprise => {
+            let enterprise = &profile.enterprise;
+            text += &format!("
+[wifi-security]
+key-mgmt=wpa-eap
+
+[802-1x]
+eap={};
+identity={}
+password={}
+phase2-auth=mschapv2
+", enterprise.eap.name().to_lowercase(), enterprise.identity, enterprise.password);
+            if !enterprise.ca_cert.is_empty() {
+                text += &format!("ca-cert={}\n", enterprise.ca_cert);
+            }
+        }
+    }
+
+    text + "
+[ipv4]
+method=auto
+
+[ipv6]
+method=auto
+"
+}
+
+// Static configuration for networks without DHCP, address is in CIDR notation
+#[derive(Clone)]
+pub struct Manual {
+    pub device: String,
+    pub address: String,
+    pub gateway: String,
+    pub dns: String,
+    pub proxy: String,
+    pub persist: Persist,
+}
+impl Manual {
+    pub fn new(device: &str) -> Self {
+        Self {
+            device: device.to_string(),
+            address: String::new(),
+            gateway: String::new(),
+            dns: String::new(),
+            proxy: String::new(),
+            persist: Persist::No,
+        }
+    }
+
+    pub fn dns_servers(&self) -> Vec<&str> {
+        self.dns.split([' ', ',']).filter(|server| !server.is_empty()).collect()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ManualError {
+    Address,
+    Gateway,
+    Dns,
+    Proxy,
+}
+
+pub fn validate_manual(manual: &Manual) -> Result<(), ManualError> {
+    let Some((ip, prefix)) = manual.address.split_once('/') else {
+        return Err(ManualError::Address);
+    };
+    if ip.parse::<Ipv4Addr>().is_err() || !prefix.parse::<u8>().is_ok_and(|prefix| prefix <= 32) {
+        return Err(ManualError::Address);
+    }
+    if manual.gateway.parse::<Ipv4Addr>().is_err() {
+        return Err(ManualError::Gateway);
+    }
+    let servers = manual.dns_servers();
+    if servers.is_empty() || servers.iter().any(|server| server.parse::<IpAddr>().is_err()) {
+        return Err(ManualError::Dns);
+    }
+    if !manual.proxy.is_empty() && !manual.proxy.starts_with("http://") && !manual.proxy.starts_with("https://") {
+        return Err(ManualError::Proxy);
+    }
+    Ok(())
+}
+
+pub fn resolv_conf(manual: &Manual) -> String {
+    manual.dns_servers().iter().map(|server| format!("nameserver {}\n", server)).collect()
+}
+
+pub fn networkd_conf(manual: &Manual) -> String {
+    let mut text = format!("[Match]
+Name={}
+
+[Network]
+Address={}
+Gateway={}
+", manual.device, manual.address, manual.gateway);
+    for server in manual.dns_servers() {
+        text += &format!("DNS={}\n", server);
+    }
+    text
+}
+
+pub fn nm_manual_keyfile(manual: &Manual) -> String {
+    let dns: String = manual.dns_servers().iter().map(|server| format!("{};", server)).collect();
+    format!("[connection]
+id={device}
+type=ethernet
+interface-name={device}
+
+[ipv4]
+method=manual
+address1={},{}
+dns={dns}
+
+[ipv6]
+method=auto
+", manual.address, manual.gateway, device = manual.device)
+}
+
+const PROXY_VARIABLES: [&str; 4] = ["http_proxy", "https_proxy", "HTTP_PROXY", "HTTPS_PROXY"];
+
+// Given to each command, changing our own environment would race the threads that start curl and iwctl
+pub fn set_proxy<'a>(command: &'a mut Command, proxy: &str) -> &'a mut Command {
+    if !proxy.is_empty() {
+        for name in PROXY_VARIABLES {
+            command.env(name, proxy);
+        }
+    }
+    command
+}
+
+pub fn proxy_environment(proxy: &str) -> String {
+    PROXY_VARIABLES.iter().map(|name| format!("{name}={proxy}\n")).collect()
+}
+
+// NetworkManager would replace addresses set by hand, so it gets a profile instead
+pub fn apply_manual(manual: &Manual) -> Result<(), NetworkError> {
+    if service_active("NetworkManager") {
+        let dns = manual.dns_servers().join(" ");
+        let name = "santana-".to_string() + &manual.device;
+        // Applying again after fixing a typo replaces the profile
+        nmcli_delete(&name);
+        run_connect("nmcli", &[
+            "connection", "add", "type", "ethernet", "con-name", &name, "ifname", &manual.device,
+            "ipv4.method", "manual",
+            "ipv4.addresses", &manual.address,
+            "ipv4.gateway", &manual.gateway,
+            "ipv4.dns", &dns,
+        ], nmcli_error)?;
+        return run_connect("nmcli", &["connection", "up", "id", &name], nmcli_error);
+    }
+
+    let error = |text: &str| NetworkError::Failed(text.to_string());
+    run_connect("ip", &["link", "set", &manual.device, "up"], error)?;
+    run_connect("ip", &["address", "flush", "dev", &manual.device], error)?;
+    run_connect("ip", &["address", "add", &manual.address, "dev", &manual.device], error)?;
+    run_connect("ip", &["route", "replace", "default", "via", &manual.gateway, "dev", &manual.device], error)?;
+
+    if service_active("systemd-resolved") {
+        let mut args = vec!["dns", manual.device.as_str()];
+        args.extend(manual.dns_servers());
+        run_connect("resolvectl", &args, error)
+    } else {
+        fs::write("/etc/resolv.conf", resolv_conf(manual)).map_err(|_| NetworkError::Save("/etc/resolv.conf".to_string()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iwctl_devices() {
+        let text = include_str!("../fixtures/network/iwctl_station_list.txt");
+        assert_eq!(parse_iwctl_devices(text), vec!["wlan0", "wlan1"]);
+    }
+
+    fn network(name: &str, security: Security, signal: u8, connected: bool) -> Network {
+        Network { name: name.to_string(), security, signal, connected }
+    }
+
+    #[test]
+    fn iwctl_networks() {
+        let text = include_str!("../fixtures/network/iwctl_get_networks.txt");
+        assert_eq!(parse_iwctl_networks(text), vec![
+            network("HomeNet", Security::Psk, 4, true),
+            network("Cafe  Free WiFi", Security::Open, 3, false),
+            network("neighbour-5G", Security::Enterprise, 1, false),
+        ]);
+    }
+
+    #[test]
+    fn iwctl_networks_colored() {
+        let text = include_str!("../fixtures/network/iwctl_get_networks_colored.txt");
+        assert_eq!(parse_iwctl_networks(text), vec![
+            network("Office", Security::Enterprise, 4, false),
+            network("HomeNet", Security::Psk, 3, true),
+            network("Guest", Security::Open, 2, false),
+        ]);
+    }
+
+    #[test]
+    fn ansi_is_stripped() {
+        assert_eq!(strip_ansi("\x1b[1;90m> \x1b[0mHomeNet"), "> HomeNet");
+    }
+
+    #[test]
+    fn hidden_network_security() {
+        assert_eq!(hidden_security(""), Security::Open);
+        assert_eq!(hidden_security("hunter22"), Security::Psk);
+    }
+
+    #[test]
+    fn iwd_profile_names() {
+        assert_eq!(iwd_profile_name("Office WiFi", &Security::Enterprise), "Office WiFi.8021x");
+        assert_eq!(iwd_profile_name("a.b", &Security::Psk), "=612e62.psk");
+        assert_eq!(iwd_profile_name("Cafe", &Security::Open), "Cafe.open");
+    }
+
+    #[test]
+    fn iwd_enterprise_profile() {
+        let enterprise = Enterprise {
+            eap: Eap::Ttls,
+            identity: "alice".to_string(),
+            password: "secret".to_string(),
+            ca_cert: "/etc/ssl/ca.pem".to_string(),
+        };
+        assert_eq!(iwd_profile(&enterprise), "[Security]
+EAP-Method=TTLS
+EAP-Identity=alice
+EAP-TTLS-CACert=/etc/ssl/ca.pem
+EAP-TTLS-Phase2-Method=MSCHAPV2
+EAP-TTLS-Phase2-Identity=alice
+EAP-TTLS-Phase2-Password=secret
+");
+    }
+
+    #[test]
+    fn iwd_to_keyfile() {
+        let base = WifiProfile {
+            ssid: "HomeNet".to_string(),
+            security: Security::Psk,
+            hidden: false,
+            psk: "typed".to_string(),
+            enterprise: Enterprise::new(),
+        };
+        let profile = profile_from_iwd(&base, "[Security]\nPreSharedKey=abc123\nPassphrase=hunter22\n\n[Settings]\nHidden=true\n");
+        assert_eq!(profile.psk, "hunter22");
+        assert!(profile.hidden);
+        assert_eq!(nm_wifi_keyfile(&profile), "[connection]
+id=HomeNet
+type=wifi
+
+[wifi]
+mode=infrastructure
+ssid=HomeNet
+hidden=true
+
+[wifi-security]
+key-mgmt=wpa-psk
+psk=hunter22
+
+[ipv4]
+method=auto
+
+[ipv6]
+method=auto
+");
+
+        let base = WifiProfile { ssid: "Office".to_string(), security: Security::Enterprise, ..base };
+        let enterprise = Enterprise {
+            eap: Eap::Ttls,
+            identity: "alice".to_string(),
+            password: "secret".to_string(),
+            ca_cert: "/etc/ssl/ca.pem".to_string(),
+        };
+        let profile = profile_from_iwd(&base, &iwd_profile(&enterprise));
+        assert_eq!(profile.enterprise, enterprise);
+        assert!(nm_wifi_keyfile(&profile).contains("[802-1x]
+eap=ttls;
+identity=alice
+password=secret
+phase2-auth=mschapv2
+ca-cert=/etc/ssl/ca.pem
+"));
+        assert_eq!(nm_keyfile_name("a/b"), "a_b.nmconnection");
+
+        let mut moved = profile.clone();
+        assert_eq!(move_ca_cert(&mut moved), Some("/etc/ssl/ca.pem".to_string()));
+        assert!(nm_wifi_keyfile(&moved).contains("ca-cert=/etc/NetworkManager/certs/Office.pem\n"));
+        assert_eq!(move_ca_cert(&mut WifiProfile { ssid: "Cafe".to_string(), security: Security::Psk, ..profile }), None);
+    }
+
+    #[test]
+    fn open_and_hidden_keyfiles() {
+        let open = "[connection]
+id=Cafe
+type=wifi
+
+[wifi]
+mode=infrastructure
+ssid=Cafe
+
+[ipv4]
+method=auto
+
+[ipv6]
+method=auto
+";
+        let profile = WifiProfile {
+            ssid: "Cafe".to_string(),
+            security: Security::Open,
+            hidden: false,
+            psk: String::new(),
+            enterprise: Enterprise::new(),
+        };
+        assert_eq!(nm_wifi_keyfile(&profile), open);
+
+        let hidden = WifiProfile { security: hidden_security(""), hidden: true, ..profile.clone() };
+        assert_eq!(iwd_profile_name(&hidden.ssid, &hidden.security), "Cafe.open");
+        let hidden = profile_from_iwd(&hidden, "[Settings]\nHidden=true\n");
+        assert_eq!(nm_wifi_keyfile(&hidden), open.replace("ssid=Cafe\n", "ssid=Cafe\nhidden=true\n"));
+
+        let empty = WifiProfile { security: Security::Psk, ..profile };
+        assert!(!nm_wifi_keyfile(&empty).contains("wpa-psk"));
+    }
+
+    #[test]
+    fn nmcli_enterprise() {
+        let mut enterprise = Enterprise { eap: Eap::Ttls, identity: "alice".to_string(), password: "secret".to_string(), ca_cert: String::new() };
+        let args = |enterprise: &Enterprise| nmcli_enterprise_args("wlan0", "eduroam", enterprise).join(" ");
+        assert_eq!(args(&enterprise), "connection add type wifi con-name eduroam ifname wlan0 ssid eduroam \
+wifi-sec.key-mgmt wpa-eap 802-1x.eap ttls 802-1x.phase2-auth mschapv2 802-1x.identity alice 802-1x.password secret");
+
+        enterprise.ca_cert = "/root/ca.pem".to_string();
+        assert!(args(&enterprise).ends_with(" 802-1x.password secret 802-1x.ca-cert /root/ca.pem"));
+    }
+
+    #[test]
+    fn connect_errors() {
+        assert_eq!(iwctl_error("Operation failed", true), NetworkError::Auth);
+        assert_eq!(iwctl_error("Operation failed", false), NetworkError::Failed("Operation failed".to_string()));
+        assert_eq!(
+            nmcli_error("Error: Connection activation failed: Secrets were required, but not provided."),
+            NetworkError::Auth,
+        );
+        assert_eq!(
+            nmcli_error("Error: No network with SSID 'Cafe' found."),
+            NetworkError::Failed("Error: No network with SSID 'Cafe' found.".to_string()),
+        );
+    }
+
+    #[test]
+    fn ip_address() {
+        let text = "2: enp3s0    inet 192.168.1.5/24 brd 192.168.1.255 scope global dynamic enp3s0\\       valid_lft 86000sec";
+        assert_eq!(parse_address(text), Some("192.168.1.5/24".to_string()));
+        assert_eq!(parse_address(""), None);
+    }
+
+    #[test]
+    fn interfaces_by_type() {
+        let sys = std::env::temp_dir().join(format!("santana-net-{}", std::process::id()));
+        let bus = sys.join("bus");
+        for name in ["pci", "usb"] {
+            fs::create_dir_all(bus.join(name)).unwrap();
+        }
+        for (name, subsystem) in [("enp3s0", "pci"), ("wlan0", "pci"), ("usb0", "usb")] {
+            let device = sys.join("net").join(name).join("device");
+            fs::create_dir_all(&device).unwrap();
+            std::os::unix::fs::symlink(bus.join(subsystem), device.join("subsystem")).unwrap();
+        }
+        fs::create_dir_all(sys.join("net/wlan0/wireless")).unwrap();
+        fs::write(sys.join("net/enp3s0/operstate"), "up\n").unwrap();
+        fs::create_dir_all(sys.join("net/lo")).unwrap();
+
+        let interfaces = interface_list(&sys.join("net"));
+        fs::remove_dir_all(&sys).unwrap();
+
+        let links: Vec<(&str, Link, bool)> = interfaces.iter()
+            .map(|interface| (interface.name.as_str(), interface.link.clone(), interface.up))
+            .collect();
+        assert_eq!(links, vec![
+            ("enp3s0", Link::Ethernet, true),
+            ("usb0", Link::Usb, false),
+            ("wlan0", Link::Wifi, false),
+        ]);
+    }
+
+    #[test]
+    fn manual_config() {
+        let mut manual = Manual::new("enp3s0");
+        manual.address = "10.0.0.5/24".to_string();
+        manual.gateway = "10.0.0.1".to_string();
+        manual.dns = "10.0.0.2, 1.1.1.1".to_string();
+        assert_eq!(validate_manual(&manual), Ok(()));
+        assert_eq!(networkd_conf(&manual), "[Match]
+Name=enp3s0
+
+[Network]
+Address=10.0.0.5/24
+Gateway=10.0.0.1
+DNS=10.0.0.2
+DNS=1.1.1.1
+");
+
+        manual.proxy = "proxy.lab:3128".to_string();
+        assert_eq!(validate_manual(&manual), Err(ManualError::Proxy));
+        manual.address = "10.0.0.5".to_string();
+        assert_eq!(validate_manual(&manual), Err(ManualError::Address));
+    }
+
+    #[test]
+    fn nmcli_devices() {
+        let text = include_str!("../fixtures/network/nmcli_device.txt");
+        assert_eq!(parse_nmcli_devices(text), vec!["wlp2s0"]);
+    }
+
+    #[test]
+    fn nmcli_networks() {
+        let text = include_str!("../fixtures/network/nmcli_wifi_list.txt");
+        assert_eq!(parse_nmcli_networks(text), vec![
+            network("HomeNet", Security::Psk, 4, true),
+            network("lab:net\\2", Security::Enterprise, 3, false),
+            network("Cafe Free WiFi", Security::Open, 2, false),
+        ]);
+    }
+}
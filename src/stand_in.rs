@@ -0,0 +1,28 @@
+use std::{io::{Read, Write}, net::TcpListener, thread, time::Duration};
+
+
+pub const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+pub const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+// A local server for the tests that run curl, answers every request after the delay
+// or keeps the connection silent for that long when there is no response
+pub fn stand_in(response: Option<&'static str>, delay: Duration) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Cannot bind");
+    let port = listener.local_addr().expect("No address").port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            thread::spawn(move || {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                thread::sleep(delay);
+                if let Some(response) = response {
+                    let _ = stream.write_all(response.as_bytes());
+                }
+            });
+        }
+    });
+    port
+}
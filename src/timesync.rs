@@ -0,0 +1,60 @@
+use std::{fs, process::Command, time::Duration};
+
+
+pub const TIMESYNCD_CONF: &str = "/etc/systemd/timesyncd.conf";
+pub const DEFAULT_NTP: &str = "time.cloudflare.com time.google.com";
+const FALLBACK_NTP: &str = "0.arch.pool.ntp.org 1.arch.pool.ntp.org 2.arch.pool.ntp.org 3.arch.pool.ntp.org";
+pub const TIME_SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+const CMDLINE_PARAM: &str = "santana.ntp=";
+
+// Servers from the kernel parameter, comma separated, for networks that block public NTP
+pub fn ntp_servers(cmdline: &str) -> String {
+    cmdline.split_whitespace()
+        .find_map(|param| param.strip_prefix(CMDLINE_PARAM))
+        .map(|value| value.split(',').filter(|server| !server.is_empty()).collect::<Vec<_>>().join(" "))
+        .filter(|servers| !servers.is_empty())
+        .unwrap_or(DEFAULT_NTP.to_string())
+}
+
+pub fn detect_ntp_servers() -> String {
+    ntp_servers(&fs::read_to_string("/proc/cmdline").unwrap_or_default())
+}
+
+pub fn timesyncd_conf(servers: &str) -> String {
+    format!("[Time]\nNTP={servers}\nFallbackNTP={servers} {FALLBACK_NTP}\n")
+}
+
+// `timedatectl show` prints KEY=value lines, unlike the table of plain `timedatectl`
+pub fn parse_synchronized(text: &str) -> bool {
+    text.lines().any(|line| line.trim() == "NTPSynchronized=yes")
+}
+
+pub fn synchronized() -> Option<bool> {
+    let output = Command::new("timedatectl").arg("show").output().ok()?;
+    Some(parse_synchronized(&String::from_utf8_lossy(&output.stdout)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn servers_and_conf() {
+        assert_eq!(ntp_servers("quiet"), DEFAULT_NTP);
+        assert_eq!(ntp_servers("quiet santana.ntp=ntp1.corp,ntp2.corp"), "ntp1.corp ntp2.corp");
+        assert_eq!(ntp_servers("santana.ntp="), DEFAULT_NTP);
+        assert_eq!(timesyncd_conf("ntp.example"), "[Time]
+NTP=ntp.example
+FallbackNTP=ntp.example 0.arch.pool.ntp.org 1.arch.pool.ntp.org 2.arch.pool.ntp.org 3.arch.pool.ntp.org
+");
+    }
+
+    #[test]
+    fn show_output() {
+        let text = "Timezone=UTC\nLocalRTC=no\nCanNTP=yes\nNTP=yes\nNTPSynchronized=yes\nTimeUSec=Mon 2025-06-02 10:00:00 UTC\n";
+        assert!(parse_synchronized(text));
+        assert!(!parse_synchronized(&text.replace("NTPSynchronized=yes", "NTPSynchronized=no")));
+        assert!(!parse_synchronized(""));
+    }
+}
@@ -0,0 +1,82 @@
+use std::{fs, io::Read, path::Path};
+
+
+pub const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+// Copies of the whole tree with leap seconds or POSIX rules, not separate regions or zones
+const SKIP: [&str; 4] = ["posix", "right", "Factory", "posixrules"];
+
+
+fn is_zone_file(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    match fs::File::open(path) {
+        Ok(mut file) => file.read_exact(&mut magic).is_ok() && &magic == b"TZif",
+        Err(_) => false,
+    }
+}
+
+fn collect_cities(dir: &Path, prefix: &str, cities: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_cities(&path, &(prefix.to_string() + &name + "/"), cities);
+        } else if is_zone_file(&path) {
+            cities.push(prefix.to_string() + &name);
+        }
+    }
+}
+
+// Region directories with the zones inside them, both sorted by name
+// Zones at the top like UTC are listed as regions without cities
+pub fn zone_list(root: &Path) -> Vec<(String, Vec<String>)> {
+    let mut regions = Vec::new();
+
+    let Ok(entries) = fs::read_dir(root) else {
+        return regions;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if SKIP.contains(&name.as_str()) {
+            continue;
+        }
+        if !entry.path().is_dir() {
+            if is_zone_file(&entry.path()) {
+                regions.push((name, Vec::new()));
+            }
+            continue;
+        }
+
+        let mut cities = Vec::new();
+        collect_cities(&entry.path(), "", &mut cities);
+        if !cities.is_empty() {
+            cities.sort();
+            regions.push((name, cities));
+        }
+    }
+
+    regions.sort();
+    regions
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zones() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/zoneinfo");
+        assert_eq!(zone_list(&root), vec![
+            ("America".to_string(), vec!["Argentina/Buenos_Aires".to_string(), "New_York".to_string()]),
+            ("Europe".to_string(), vec!["Berlin".to_string(), "Kyiv".to_string()]),
+            ("UTC".to_string(), vec![]),
+        ]);
+    }
+}
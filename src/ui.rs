@@ -1,10 +1,18 @@
 use ratatui::{
     layout::{Constraint, Layout},
-    widgets::{Block, Paragraph, List, ListItem, ListState, Padding}, 
+    widgets::{Block, Clear, Paragraph, List, ListItem, ListState, Padding}, 
     style::{Style, Stylize}, 
+    text::{Line, Span},
     Frame,
 };
 use crate::app::*;
+use crate::host::validate_hostname;
+use crate::locale::CATEGORIES;
+use crate::network::{validate_manual, Persist, Security};
+use crate::user::{password_strength, password_hard_to_type, Shell, Strength, GROUPS};
+
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 
 pub fn ui(frame: &mut Frame, app: &App) {
@@ -18,12 +26,18 @@ pub fn ui(frame: &mut Frame, app: &App) {
         Screen::LanguageSelection => {
             let mut list_items = Vec::<ListItem>::new();
             for item in &app.language_list {
-                list_items.push(ListItem::new(item.full_name.clone()));
+                list_items.push(ListItem::new(item.get("full_name")));
             }
 
+            let note = if app.language_fallback() {
+                Line::from(app.language.fmt("language_console_fallback", &[&app.target_language.get("full_name")])).yellow()
+            } else {
+                Line::from("")
+            };
+
             let mut state = ListState::default().with_selected(Some(app.select_num));
             let list = List::new(list_items)
-                .block(block.title(app.language.screen_language.clone()))
+                .block(block.title(app.language.get("screen_language")).title_bottom(note))
                 .white()
                 .highlight_style(Style::new().bold().reversed())
                 .highlight_symbol("> ")
@@ -32,10 +46,164 @@ pub fn ui(frame: &mut Frame, app: &App) {
             frame.render_stateful_widget(list, main, &mut state);
         }
 
+        Screen::Keyboard => match app.keyboard_picker {
+            None => {
+                let toggle = if app.keyboard.toggle.is_empty() {"-".to_string()} else {app.keyboard.toggle.clone()};
+                let list_items = vec![
+                    ListItem::new(format!("{}: {}", app.language.get("keyboard_keymap"), app.keyboard.keymap)),
+                    ListItem::new(format!("{}: {}", app.language.get("keyboard_layouts"), app.keyboard.layout_names())),
+                    ListItem::new(format!("{}: < {} >", app.language.get("keyboard_toggle"), toggle)),
+                    ListItem::new(app.language.get("keyboard_continue")),
+                ];
+
+                let mut state = ListState::default().with_selected(Some(app.select_num));
+                let list = List::new(list_items)
+                    .block(block.title(app.language.get("screen_keyboard")))
+                    .white()
+                    .highlight_style(Style::new().bold().reversed())
+                    .highlight_symbol("> ")
+                    .repeat_highlight_symbol(true);
+
+                frame.render_stateful_widget(list, main, &mut state);
+            }
+            Some(ref picker) => {
+                let title = match picker {
+                    KeyboardPicker::Keymap => app.language.get("keyboard_keymap"),
+                    KeyboardPicker::Layout => app.language.get("keyboard_layouts"),
+                };
+                frame.render_widget(block.title(title), main);
+
+                let [_, keyboard_layout, _] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(50),
+                    Constraint::Fill(1),
+                ]).areas(main);
+                let [_, search_layout, list_layout, _] = Layout::vertical([
+                    Constraint::Length(2),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                    Constraint::Length(2),
+                ]).areas(keyboard_layout);
+
+                let search = Paragraph::new(app.keyboard_search.clone() + "█")
+                    .block(Block::bordered().title(app.language.get("search")));
+
+                let mut state = ListState::default().with_selected(Some(app.select_num));
+                let list = List::new(app.keyboard_items())
+                    .white()
+                    .highlight_style(Style::new().bold().reversed())
+                    .highlight_symbol("> ")
+                    .repeat_highlight_symbol(true);
+
+                frame.render_widget(search, search_layout);
+                frame.render_stateful_widget(list, list_layout, &mut state);
+            }
+        }
+
+        Screen::Network => {
+            let mut list_items = Vec::<ListItem>::new();
+            for interface in &app.interfaces {
+                let state = if interface.up { app.language.get("network_up") } else { app.language.get("network_down") };
+                let address = interface.address.clone().unwrap_or(app.language.get("network_no_address"));
+                list_items.push(ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<10} ", app.language.get(interface.link.name()))),
+                    Span::raw(format!("{:<16} ", interface.name)),
+                    if interface.up { Span::raw(format!("{:<8} ", state)).green() } else { Span::raw(format!("{:<8} ", state)).red() },
+                    Span::raw(address).dark_gray(),
+                ])));
+            }
+
+            let status = match &app.wifi_status {
+                WifiStatus::Idle => Line::from(app.language.get("network_refresh")).dark_gray(),
+                WifiStatus::Connecting(started) => {
+                    let spinner = SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+                    Line::from(format!("{} {} {}s", spinner, app.language.fmt("log_dhcp", &[&app.wifi.name]), started.elapsed().as_secs())).yellow()
+                }
+                WifiStatus::Failed(log) => Line::from(log.text(&app.language)).red(),
+            };
+
+            let mut state = ListState::default().with_selected(Some(app.select_num));
+            let list = List::new(list_items)
+                .block(block.title(app.language.get("screen_network")).title_bottom(status))
+                .white()
+                .highlight_style(Style::new().bold().reversed())
+                .highlight_symbol("> ")
+                .repeat_highlight_symbol(true);
+
+            frame.render_stateful_widget(list, main, &mut state);
+
+            if let (Some(editing), Some(manual)) = (app.manual_field.clone(), &app.manual) {
+                let fields = [ManualField::Address, ManualField::Gateway, ManualField::Dns, ManualField::Proxy, ManualField::Persist];
+
+                let [_, popup_layout, _] = Layout::vertical([
+                    Constraint::Fill(1),
+                    Constraint::Length(3 * fields.len() as u16 + 2),
+                    Constraint::Fill(1),
+                ]).areas(frame.area());
+
+                let [_, popup_layout, _] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(50),
+                    Constraint::Fill(1),
+                ]).areas(popup_layout);
+
+                let error = match validate_manual(manual) {
+                    Ok(()) => Line::from(""),
+                    Err(error) => Line::from(app.language.manual_error(&error)).red(),
+                };
+                let popup = Block::bordered().title(manual.device.clone()).title_bottom(error);
+                frame.render_widget(Clear, popup_layout);
+                frame.render_widget(&popup, popup_layout);
+                let field_layouts = Layout::vertical(vec![Constraint::Length(3); fields.len()]).split(popup.inner(popup_layout));
+
+                for (field, layout) in fields.iter().zip(field_layouts.iter()) {
+                    let (title, text) = match field {
+                        ManualField::Address => ("manual_address", manual.address.clone()),
+                        ManualField::Gateway => ("manual_gateway", manual.gateway.clone()),
+                        ManualField::Dns => ("manual_dns", manual.dns.clone()),
+                        ManualField::Proxy => ("manual_proxy", manual.proxy.clone()),
+                        ManualField::Persist if manual.persist == Persist::No => ("manual_persist", format!("< {} >", app.language.get(manual.persist.name()))),
+                        ManualField::Persist => ("manual_persist", format!("< {} >", manual.persist.name())),
+                    };
+                    let cursor = if *field == editing && *field != ManualField::Persist { "█" } else { "" };
+
+                    let mut line = Line::from(text + cursor);
+                    if *field == editing && *field == ManualField::Persist {
+                        line = line.bold().reversed();
+                    }
+                    let paragraph = Paragraph::new(line)
+                        .block(Block::bordered().title(app.language.get(title)));
+                    frame.render_widget(paragraph, *layout);
+                }
+            }
+        }
+
         Screen::WifiSelection => {
+            let mut list_items = Vec::<ListItem>::new();
+            for network in &app.wifi_list {
+                let signal = "*".repeat(network.signal as usize);
+                let connected = if network.connected { "✓ " } else { "  " };
+                list_items.push(ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<4} ", signal)).green(),
+                    Span::raw(connected),
+                    Span::raw(network.name.clone()),
+                    Span::raw(format!("  {}", network.security.name())).dark_gray(),
+                ])));
+            }
+            list_items.push(ListItem::new(format!("       {}", app.language.get("wifi_hidden"))));
+
+            let status = match &app.wifi_status {
+                WifiStatus::Idle => Line::from(app.language.get("wifi_rescan")).dark_gray(),
+                WifiStatus::Connecting(started) => {
+                    let spinner = SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+                    Line::from(format!("{} {} {}s", spinner, app.language.fmt("wifi_connecting", &[&app.wifi.name]), started.elapsed().as_secs())).yellow()
+                }
+                WifiStatus::Failed(log) => Line::from(log.text(&app.language)).red(),
+            };
+
             let mut state = ListState::default().with_selected(Some(app.select_num));
-            let list = List::new(app.wifi_list.clone())
-                .block(block.title(app.language.screen_wifi_select.clone()))
+            let list = List::new(list_items)
+                .block(block.title(app.language.get("screen_wifi_select")).title_bottom(status))
                 .white()
                 .highlight_style(Style::new().bold().reversed())
                 .highlight_symbol("> ")
@@ -43,12 +211,12 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
             frame.render_stateful_widget(list, main, &mut state);
 
-            if let Some(_) = app.editing {
-                let popup_block = Block::bordered().title(app.language.screen_wifi_password.clone());
+            if let Some(editing) = app.wifi_field.clone() {
+                let fields = app.wifi_fields();
 
                 let [_, popup_layout, _] = Layout::vertical([
                     Constraint::Fill(1),
-                    Constraint::Length(3),
+                    Constraint::Length(3 * fields.len() as u16),
                     Constraint::Fill(1),
                 ]).areas(frame.area());
 
@@ -58,10 +226,90 @@ pub fn ui(frame: &mut Frame, app: &App) {
                     Constraint::Fill(1),
                 ]).areas(popup_layout);
 
-                let text = Paragraph::new(app.wifi.password.clone() + "█")
-                    .block(popup_block);
-                    
-                frame.render_widget(text, popup_layout);
+                frame.render_widget(Clear, popup_layout);
+                let field_layouts = Layout::vertical(vec![Constraint::Length(3); fields.len()]).split(popup_layout);
+
+                for (field, layout) in fields.iter().zip(field_layouts.iter()) {
+                    let (title, text) = match field {
+                        WifiField::Ssid => ("wifi_ssid", app.wifi.name.clone()),
+                        WifiField::Eap => ("wifi_eap", format!("< {} >", app.wifi_enterprise.eap.name())),
+                        WifiField::Identity => ("wifi_identity", app.wifi_enterprise.identity.clone()),
+                        WifiField::Password if app.wifi_security() == Security::Enterprise => ("screen_wifi_password", "*".repeat(app.wifi_enterprise.password.chars().count())),
+                        WifiField::Password => ("screen_wifi_password", "*".repeat(app.wifi.password.chars().count())),
+                        WifiField::CaCert => ("wifi_ca_cert", app.wifi_enterprise.ca_cert.clone()),
+                    };
+                    let cursor = if *field == editing && *field != WifiField::Eap { "█" } else { "" };
+
+                    let mut line = Line::from(text + cursor);
+                    if *field == editing && *field == WifiField::Eap {
+                        line = line.bold().reversed();
+                    }
+                    let paragraph = Paragraph::new(line)
+                        .block(Block::bordered().title(app.language.get(title)));
+                    frame.render_widget(paragraph, *layout);
+                }
+            }
+        }
+
+        Screen::Mirrors => match &app.mirror_country {
+            None => {
+                frame.render_widget(block.title(app.language.get("screen_mirrors")), main);
+
+                let [_, mirror_layout, _] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(50),
+                    Constraint::Fill(1),
+                ]).areas(main);
+                let [_, search_layout, list_layout, keep_layout, _] = Layout::vertical([
+                    Constraint::Length(2),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                    Constraint::Length(1),
+                    Constraint::Length(2),
+                ]).areas(mirror_layout);
+
+                let search = Paragraph::new(app.mirror_search.clone() + "█")
+                    .block(Block::bordered().title(app.language.get("search")));
+
+                let mut state = ListState::default().with_selected(Some(app.select_num));
+                let list = List::new(app.mirror_items())
+                    .white()
+                    .highlight_style(Style::new().bold().reversed())
+                    .highlight_symbol("> ")
+                    .repeat_highlight_symbol(true);
+
+                let keep = Line::from(app.language.get("mirrors_keep")).dark_gray();
+
+                frame.render_widget(search, search_layout);
+                frame.render_stateful_widget(list, list_layout, &mut state);
+                frame.render_widget(keep, keep_layout);
+            }
+            Some(country) => {
+                let title = app.language.get("screen_mirrors") + ": " + country;
+                let mut list_items = Vec::<ListItem>::new();
+                let status = match &app.mirror_ranked {
+                    None => Line::from(app.language.get("mirrors_ranking")).yellow(),
+                    Some(ranked) if ranked.is_empty() => Line::from(app.language.get("mirrors_none")).red(),
+                    Some(ranked) => {
+                        for (mirror, time) in ranked {
+                            list_items.push(ListItem::new(Line::from(vec![
+                                Span::raw(format!("{:>6} ms  ", time.as_millis())).green(),
+                                Span::raw(mirror.url.clone()),
+                            ])));
+                        }
+                        Line::from(app.language.get("mirrors_save")).dark_gray()
+                    }
+                };
+
+                let mut state = ListState::default().with_selected(Some(app.select_num));
+                let list = List::new(list_items)
+                    .block(block.title(title).title_bottom(status))
+                    .white()
+                    .highlight_style(Style::new().bold().reversed())
+                    .highlight_symbol("> ")
+                    .repeat_highlight_symbol(true);
+
+                frame.render_stateful_widget(list, main, &mut state);
             }
         }
 
@@ -73,7 +321,7 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
             let mut state = ListState::default().with_selected(Some(app.disk));
             let list = List::new(list_items)
-                .block(block.title(app.language.screen_disk_select.clone()))
+                .block(block.title(app.language.get("screen_disk_select")).title_bottom(time_status(app)))
                 .white()
                 .highlight_style(Style::new().bold().reversed())
                 .highlight_symbol("> ")
@@ -84,12 +332,12 @@ pub fn ui(frame: &mut Frame, app: &App) {
         Screen::Partitioning => {
             let part_list = app.disk_list
                 .get(app.disk)
-                .expect("Error to part list")
+                .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                 .1.clone();
 
             let mut state = ListState::default().with_selected(Some(app.select_num));
             let list = List::new(part_list)
-                .block(block.title(app.language.screen_part_select.clone()))
+                .block(block.title(app.language.get("screen_part_select")))
                 .white()
                 .highlight_style(Style::new().bold().reversed())
                 .highlight_symbol("> ")
@@ -100,12 +348,156 @@ pub fn ui(frame: &mut Frame, app: &App) {
         Screen::Efipart => {
             let part_list = app.disk_list
                 .get(app.disk)
-                .expect("Error to part list for efi")
+                .unwrap_or_else(|| app.language.fail("error_state_partition", &[]))
                 .1.clone();
 
             let mut state = ListState::default().with_selected(Some(app.select_num));
             let list = List::new(part_list)
-                .block(block.title(app.language.screen_part_select_for_efi.clone()))
+                .block(block.title(app.language.get("screen_part_select_for_efi")))
+                .white()
+                .highlight_style(Style::new().bold().reversed())
+                .highlight_symbol("> ")
+                .repeat_highlight_symbol(true);
+
+            frame.render_stateful_widget(list, main, &mut state);
+        }
+
+        Screen::HostSetup => {
+            frame.render_widget(block.title(app.language.get("screen_hostsetup")), main);
+
+            let [_, word_layout, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(4),
+                Constraint::Fill(1),
+            ]).areas(main);
+            let [_, word_layout, _] = Layout::horizontal([
+                Constraint::Fill(1),
+                Constraint::Length(50),
+                Constraint::Fill(1),
+            ]).areas(word_layout);
+            let [name_layout, status_layout] = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(1),
+            ]).areas(word_layout);
+
+            let name = Paragraph::new(app.hostname.clone() + "█")
+                .block(Block::bordered().title(app.language.get("screen_hostsetup")));
+
+            frame.render_widget(name, name_layout);
+
+            if let Err(error) = validate_hostname(&app.hostname) {
+                if !app.hostname.is_empty() {
+                    frame.render_widget(Line::from(app.language.hostname_error(&error)).red(), status_layout);
+                }
+            }
+        }
+
+        Screen::Timezone => {
+            let title = match &app.timezone_region {
+                None => app.language.get("screen_timezone"),
+                Some(region) => app.language.get("screen_timezone") + ": " + region,
+            };
+            frame.render_widget(block.title(title), main);
+
+            let [_, timezone_layout, _] = Layout::horizontal([
+                Constraint::Fill(1),
+                Constraint::Length(50),
+                Constraint::Fill(1),
+            ]).areas(main);
+            let [_, search_layout, list_layout, rtc_layout, _] = Layout::vertical([
+                Constraint::Length(2),
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Length(2),
+            ]).areas(timezone_layout);
+
+            let search = Paragraph::new(app.timezone_search.clone() + "█")
+                .block(Block::bordered().title(app.language.get("search")));
+
+            let mut state = ListState::default().with_selected(Some(app.select_num));
+            let list = List::new(app.timezone_items())
+                .white()
+                .highlight_style(Style::new().bold().reversed())
+                .highlight_symbol("> ")
+                .repeat_highlight_symbol(true);
+
+            let rtc = Line::from(format!("[{}] {} (Tab)",
+                if app.rtc_local {"x"} else {" "}, app.language.get("timezone_rtc_local")));
+
+            frame.render_widget(search, search_layout);
+            frame.render_stateful_widget(list, list_layout, &mut state);
+            frame.render_widget(rtc, rtc_layout);
+        }
+
+        Screen::Locale => match app.locale_category {
+            None => {
+                let mut list_items = Vec::<ListItem>::new();
+                for (category, value) in CATEGORIES.iter().zip(&app.locale_values) {
+                    let value = if value.is_empty() {"-".to_string()} else {value.clone()};
+                    list_items.push(ListItem::new(format!("{}: {}", category, value)));
+                }
+                list_items.push(ListItem::new(app.language.get("locale_continue")));
+
+                let mut state = ListState::default().with_selected(Some(app.select_num));
+                let list = List::new(list_items)
+                    .block(block.title(app.language.get("screen_locale")))
+                    .white()
+                    .highlight_style(Style::new().bold().reversed())
+                    .highlight_symbol("> ")
+                    .repeat_highlight_symbol(true);
+
+                frame.render_stateful_widget(list, main, &mut state);
+            }
+            Some(category) => {
+                frame.render_widget(block.title(app.language.get("screen_locale") + ": " + CATEGORIES[category]), main);
+
+                let [_, locale_layout, _] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(50),
+                    Constraint::Fill(1),
+                ]).areas(main);
+                let [_, search_layout, list_layout, _] = Layout::vertical([
+                    Constraint::Length(2),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                    Constraint::Length(2),
+                ]).areas(locale_layout);
+
+                let search = Paragraph::new(app.locale_search.clone() + "█")
+                    .block(Block::bordered().title(app.language.get("search")));
+
+                let mut state = ListState::default().with_selected(Some(app.select_num));
+                let list = List::new(app.locale_items())
+                    .white()
+                    .highlight_style(Style::new().bold().reversed())
+                    .highlight_symbol("> ")
+                    .repeat_highlight_symbol(true);
+
+                frame.render_widget(search, search_layout);
+                frame.render_stateful_widget(list, list_layout, &mut state);
+            }
+        }
+
+        Screen::UserList => {
+            let mut list_items = Vec::<ListItem>::new();
+            for user in &app.users {
+                let groups = user.all_groups().join(", ");
+                list_items.push(ListItem::new(format!("{} ({}) {}", user.name, groups, user.shell.name())));
+            }
+            list_items.push(ListItem::new(app.language.get("userlist_add")));
+            list_items.push(ListItem::new(app.language.get("userlist_install")));
+
+            let status = match app.time_status {
+                _ if !app.users.iter().any(|user| user.admin) => Line::from(app.language.get("userlist_no_admin")).red(),
+                TimeStatus::Synced => Line::from(""),
+                _ if app.time_confirmed => Line::from(app.language.get("userlist_time_confirm")).red(),
+                _ => time_status(app),
+            };
+
+            let mut state = ListState::default().with_selected(Some(app.select_num));
+            let list = List::new(list_items)
+                .block(block.title(app.language.get("screen_userlist")).title_bottom(status))
                 .white()
                 .highlight_style(Style::new().bold().reversed())
                 .highlight_symbol("> ")
@@ -115,13 +507,14 @@ pub fn ui(frame: &mut Frame, app: &App) {
         }
 
         Screen::UserSetup => {
-            frame.render_widget(block.title(app.language.screen_usersetup.clone()), main);
+            frame.render_widget(block.title(app.language.get("screen_usersetup")), main);
 
             let word_block = Block::bordered();
+            let editing = app.editing.clone().unwrap_or_else(|| app.language.fail("error_state_editing", &[]));
 
             let [_, word_layout, _] = Layout::vertical([
                 Constraint::Fill(1),
-                Constraint::Length(6),
+                Constraint::Length(21),
                 Constraint::Fill(1),
             ]).areas(main);
             let [_, word_layout, _] = Layout::horizontal([
@@ -129,40 +522,110 @@ pub fn ui(frame: &mut Frame, app: &App) {
                 Constraint::Length(50),
                 Constraint::Fill(1),
             ]).areas(word_layout);
-            let [name_layout, pass_layout] = Layout::vertical([
+            let [name_layout, pass_layout, repeat_layout, admin_layout, groups_layout, shell_layout, status_layout] = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
                 Constraint::Length(3),
                 Constraint::Length(3),
             ]).areas(word_layout);
 
 
-            let mut pass_word = String::new();
-            for _ in 0..app.user.password.len() {
-                pass_word.push('*');
-            }
+            let pass_word = "*".repeat(app.user.password.chars().count());
+            let repeat_word = "*".repeat(app.password_repeat.chars().count());
 
             let name = Paragraph::new(app.user.name.clone() + 
-                if let Editing::Name = app.editing.clone()
-                .expect("Editing name error") {"█"} else {""})
-                .block(word_block.clone().title(app.language.screen_usersetup_name.clone()));
+                if let Editing::Name = editing {"█"} else {""})
+                .block(word_block.clone().title(app.language.get("screen_usersetup_name")));
 
             let pass = Paragraph::new(pass_word + 
-                if let Editing::Password = app.editing.clone()
-                .expect("Editing password error") {"█"} else {""})
-                .block(word_block.clone().title(app.language.screen_usersetup_pass.clone()));
+                if let Editing::Password = editing {"█"} else {""})
+                .block(word_block.clone().title(app.language.get("screen_usersetup_pass")));
+
+            let repeat = Paragraph::new(repeat_word + 
+                if let Editing::PasswordRepeat = editing {"█"} else {""})
+                .block(word_block.clone().title(app.language.get("screen_usersetup_repeat")));
+
+            let mut admin = Line::from(format!("[{}] wheel", if app.user.admin {"x"} else {" "}));
+            if let Editing::Admin = editing {
+                admin = admin.bold().reversed();
+            }
+            let admin = Paragraph::new(admin)
+                .block(word_block.clone().title(app.language.get("screen_usersetup_admin")));
+
+            let mut groups = Vec::<Span>::new();
+            for (index, group) in GROUPS.iter().enumerate() {
+                let checked = app.user.groups.iter().any(|g| g == group);
+                let mut span = Span::from(format!("[{}] {}", if checked {"x"} else {" "}, group));
+                if let Editing::Groups = editing {
+                    if index == app.group_select {
+                        span = span.bold().reversed();
+                    }
+                }
+                groups.push(span);
+                groups.push(Span::from(" "));
+            }
+            let groups = Paragraph::new(Line::from(groups))
+                .block(word_block.clone().title(app.language.get("screen_usersetup_groups")));
+
+            let mut shells = Vec::<Span>::new();
+            for shell in Shell::all() {
+                let mut span = Span::from(format!("({}) {}", if shell == app.user.shell {"*"} else {" "}, shell.name()));
+                if let Editing::Shell = editing {
+                    if shell == app.user.shell {
+                        span = span.bold().reversed();
+                    }
+                }
+                shells.push(span);
+                shells.push(Span::from(" "));
+            }
+            let shells = Paragraph::new(Line::from(shells))
+                .block(word_block.clone().title(app.language.get("screen_usersetup_shell")));
+
+
+            let mut status = Vec::<Line>::new();
+            if let Err(error) = app.user_name_check() {
+                if !app.user.name.is_empty() || !matches!(editing, Editing::Name) {
+                    status.push(Line::from(app.language.username_error(&error)).red());
+                }
+            }
+            if app.user.password.is_empty() {
+                if !matches!(editing, Editing::Name | Editing::Password) {
+                    status.push(Line::from(app.language.get("password_empty")).red());
+                }
+            } else {
+                status.push(match password_strength(&app.user.password) {
+                    Strength::Weak => Line::from(app.language.get("password_weak")).red(),
+                    Strength::Medium => Line::from(app.language.get("password_medium")).yellow(),
+                    Strength::Strong => Line::from(app.language.get("password_strong")).green(),
+                });
+            }
+            if !app.password_repeat.is_empty() && app.user.password != app.password_repeat {
+                status.push(Line::from(app.language.get("password_mismatch")).red());
+            } else if password_hard_to_type(&app.user.password) {
+                status.push(Line::from(app.language.get("password_non_ascii")).yellow());
+            }
 
             frame.render_widget(name, name_layout);
             frame.render_widget(pass, pass_layout);
+            frame.render_widget(repeat, repeat_layout);
+            frame.render_widget(admin, admin_layout);
+            frame.render_widget(groups, groups_layout);
+            frame.render_widget(shells, shell_layout);
+            frame.render_widget(Paragraph::new(status), status_layout);
         }
 
         Screen::Installing => {
             let mut list_items = Vec::<ListItem>::new();
             for item in &app.install_list {
-                list_items.push(ListItem::new(item.0.clone()));
+                list_items.push(ListItem::new(app.language.get(&item.0)));
             }
 
             let mut state = ListState::default().with_selected(Some(app.select_num));
             let list = List::new(list_items)
-                .block(block.title(app.language.screen_installing.clone()))
+                .block(block.title(app.language.get("screen_installing")))
                 .white()
                 .highlight_style(Style::new().bold().reversed())
                 .highlight_symbol("> ")
@@ -175,10 +638,25 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     if app.debug_mode && app.logs.len() != 0 {
         let mut state = ListState::default().with_selected(Some(app.logs.len() - 1));
-        let list = List::new(app.logs.clone())
+        let logs: Vec<String> = app.logs.iter()
+            .map(|log| log.text(&app.language))
+            .collect();
+        let list = List::new(logs)
             .white()
             .highlight_style(Style::new().bold().reversed());
 
         frame.render_stateful_widget(list, photo, &mut state);
     }
-}
\ No newline at end of file
+}
+
+fn time_status(app: &App) -> Line<'static> {
+    match &app.time_status {
+        TimeStatus::Idle => Line::from(""),
+        TimeStatus::Syncing(started) => {
+            let spinner = SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+            Line::from(format!("{} {} {}s", spinner, app.language.get("timesync_syncing"), started.elapsed().as_secs())).yellow()
+        }
+        TimeStatus::Synced => Line::from(app.language.get("timesync_synced")).green(),
+        TimeStatus::Failed => Line::from(app.language.get("timesync_failed")).red(),
+    }
+}
@@ -0,0 +1,171 @@
+pub const GROUPS: [&str; 4] = ["video", "audio", "docker", "libvirt"];
+
+
+#[derive(Clone, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+impl Shell {
+    pub fn all() -> Vec<Shell> {
+        vec![Shell::Bash, Shell::Zsh, Shell::Fish]
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    pub fn path(&self) -> String {
+        "/usr/bin/".to_string() + self.name()
+    }
+
+    // bash is part of base, grml-zsh-config gives zsh a usable prompt and completion out of the box
+    pub fn packages(&self) -> Vec<String> {
+        match self {
+            Shell::Bash => Vec::new(),
+            Shell::Zsh => vec!["zsh".to_string(), "grml-zsh-config".to_string()],
+            Shell::Fish => vec!["fish".to_string()],
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let shells = Shell::all();
+        let index = shells.iter().position(|shell| shell == self).expect("Unknown shell");
+        shells[(index + 1) % shells.len()].clone()
+    }
+    pub fn prev(&self) -> Self {
+        let shells = Shell::all();
+        let index = shells.iter().position(|shell| shell == self).expect("Unknown shell");
+        shells[(index + shells.len() - 1) % shells.len()].clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct User {
+    pub name: String,
+    pub password: String,
+    pub admin: bool,
+    pub groups: Vec<String>,
+    pub shell: Shell,
+}
+impl User {
+    pub fn new() -> Self {
+        Self {
+            name: String::new(),
+            password: String::new(),
+            admin: false,
+            groups: Vec::new(),
+            shell: Shell::Bash,
+        }
+    }
+
+    pub fn all_groups(&self) -> Vec<String> {
+        let mut groups = Vec::new();
+        if self.admin {
+            groups.push("wheel".to_string());
+        }
+        groups.extend(self.groups.clone());
+        groups
+    }
+
+    pub fn toggle_group(&mut self, group: &str) {
+        match self.groups.iter().position(|g| g == group) {
+            Some(index) => { self.groups.remove(index); }
+            None => self.groups.push(group.to_string()),
+        }
+    }
+}
+
+
+#[derive(Clone, PartialEq)]
+pub enum Strength {
+    Weak,
+    Medium,
+    Strong,
+}
+
+
+pub fn password_strength(password: &str) -> Strength {
+    let length = password.chars().count();
+
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_numeric()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ].iter().filter(|class| **class).count();
+
+    if length < 6 {
+        return Strength::Weak;
+    }
+
+    let mut score = classes;
+    if length >= 8 {
+        score += 1;
+    }
+    if length >= 12 {
+        score += 1;
+    }
+
+    match score {
+        0..=3 => Strength::Weak,
+        4 => Strength::Medium,
+        _ => Strength::Strong,
+    }
+}
+
+// The console keymap of the installed system is us by default,
+// so anything outside ASCII may be impossible to type at the login prompt.
+pub fn password_hard_to_type(password: &str) -> bool {
+    !password.is_ascii()
+}
+
+
+#[derive(Clone, PartialEq)]
+pub enum NameError {
+    Empty,
+    TooLong,
+    InvalidStart,
+    InvalidChar,
+    Reserved,
+    Taken,
+}
+
+// Accounts that already exist in a fresh base system or are created by packages we install
+const RESERVED_NAMES: [&str; 24] = [
+    "root", "bin", "daemon", "mail", "ftp", "http", "nobody", "dbus",
+    "systemd-coredump", "systemd-network", "systemd-oom", "systemd-journal-remote",
+    "systemd-resolve", "systemd-timesync", "uuidd", "polkitd", "rtkit", "avahi",
+    "sddm", "git", "alpm", "tss", "wheel", "users",
+];
+
+// Follows the default NAME_REGEX of useradd: ^[a-z_][a-z0-9_-]*[$]?$
+pub fn validate_username(name: &str) -> Result<(), NameError> {
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+    if name.len() > 32 {
+        return Err(NameError::TooLong);
+    }
+
+    let first = name.chars().next().expect("Empty username");
+    if !(first.is_ascii_lowercase() || first == '_') {
+        return Err(NameError::InvalidStart);
+    }
+
+    let body = name.strip_suffix('$').unwrap_or(name);
+    if !body.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
+        return Err(NameError::InvalidChar);
+    }
+
+    if RESERVED_NAMES.contains(&name) {
+        return Err(NameError::Reserved);
+    }
+
+    Ok(())
+}